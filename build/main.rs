@@ -14,9 +14,11 @@
 #[macro_use] mod codegen;
              mod files;
              mod strings;
+#[path = "../src/stashes/model/item/mods/tokenize.rs"]
+             mod tokenize;
 
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::error::Error;
 use std::fs;
@@ -26,11 +28,13 @@ use std::path::Path;
 use itertools::Itertools;
 
 use self::files::create_out_file;
-use self::strings::upper_camel_case;
+use self::strings::{capitalize, upper_camel_case};
 
 
 fn main() {
     generate_currency_code().unwrap();
+    generate_item_base_code().unwrap();
+    generate_gem_code().unwrap();
     if is_feature_enabled("mods_db") {
         generate_item_mod_code().unwrap();
     }
@@ -103,6 +107,27 @@ fn generate_currency_enum(currencies: &[CurrencyData]) -> io::Result<()> {
         emit!(ctx, "{},", upper_camel_case(&currency.name))?;
     }
     ctx.end("}")?;
+
+    ctx.begin("impl Currency {")?;
+    ctx.emit("/// Full name of the currency item, e.g. \"Chaos Orb\".")?;
+    ctx.begin("pub fn name(&self) -> &'static str {")?;
+    ctx.begin("match *self {")?;
+    for currency in currencies {
+        emit!(ctx, "Currency::{} => \"{}\",", upper_camel_case(&currency.name), currency.name)?;
+    }
+    ctx.end("}")?;
+    ctx.end("}")?;
+    ctx.emit("")?;
+    ctx.emit("/// Look up the currency by the full name of its item, e.g. \"Chaos Orb\".")?;
+    ctx.begin("pub fn from_name(name: &str) -> Option<Self> {")?;
+    ctx.begin("match name {")?;
+    for currency in currencies {
+        emit!(ctx, "\"{}\" => Some(Currency::{}),", currency.name, upper_camel_case(&currency.name))?;
+    }
+    ctx.emit("_ => None,")?;
+    ctx.end("}")?;
+    ctx.end("}")?;
+    ctx.end("}")?;
     Ok(())
 }
 
@@ -129,17 +154,208 @@ fn generate_currency_de(currencies: &[CurrencyData]) -> io::Result<()> {
 }
 
 
+// Item base handling
+
+const ITEM_BASES_JSON_FILES: &[&str] = &["data/bases.json", "data/extra/bases.json"];
+const MAPS_JSON_FILE: &str = "data/maps.json";
+const ITEM_BASES_FILE: &str = "model/item/base/infos.inc.rs";
+
+/// Generate code for the list of known item bases (i.e. a `Vec<BaseInfo>`).
+///
+/// Implicits of the bases are only resolved to mods with the `mods_db` feature.
+fn generate_item_base_code() -> Result<(), Box<Error>> {
+    let mut bases = Vec::new();
+    for &path in ITEM_BASES_JSON_FILES.iter() {
+        let file = fs::OpenOptions::new().read(true).open(Path::new(".").join(path))?;
+        let data: Vec<ItemBaseData> = serde_json::from_reader(BufReader::new(file))?;
+        bases.extend(data);
+    }
+    // Maps are item bases too, but they are listed separately in the API data.
+    let file = fs::OpenOptions::new().read(true).open(Path::new(".").join(MAPS_JSON_FILE))?;
+    let maps: Vec<MapData> = serde_json::from_reader(BufReader::new(file))?;
+    bases.extend(maps.into_iter().map(|m| ItemBaseData{
+        name: m.name,
+        item_class: "Map".into(),
+        size: Some((1, 1)),
+        ..ItemBaseData::default()
+    }));
+
+    let mod_ids = if is_feature_enabled("mods_db") { Some(mod_ids_by_key("implicit")?) } else { None };
+
+    let out = create_out_file(ITEM_BASES_FILE)?;
+    let mut ctx = codegen::Context::new(out);
+    ctx.begin("vec![")?;
+    for base in bases {
+        let category = item_class_category_expr(&base.item_class)
+            .ok_or_else(|| format!("unknown item class `{}` of `{}`", base.item_class, base.name))?;
+        ctx.begin("BaseInfo {")?;
+        emit!(ctx, "name: {:?}, class: {:?}, category: {},", base.name, base.item_class, category)?;
+        emit!(ctx, "level: {:?}, size: {:?},", base.level, base.size)?;
+        emit!(ctx, "requirements: vec![{}].into_iter().collect(),",
+            base.requirements.iter().format_with(", ", |(r, v), f| {
+                f(&format_args!("(Requirement::{}, {})", requirement_variant(r), v))
+            }))?;
+        // Implicits which don't correspond to any known implicit mod make them all unknown.
+        let implicits: Option<Vec<_>> = base.implicits.as_ref().and_then(|implicits| {
+            implicits.iter().map(|imp| resolve_mod_stat(imp, mod_ids.as_ref()?)).collect()
+        });
+        match implicits {
+            Some(ref implicits) => emit!(ctx, "implicits: Some(vec![{}]),",
+                implicits.iter().format_with(", ", |&(id, ref ranges), f| {
+                    f(&format_args!("BaseImplicit{{mod_id: {}, ranges: vec![{}]}}", mod_id_expr(id),
+                        ranges.iter().format_with(", ", |&(min, max), f| {
+                            f(&format_args!("({:?}, {:?})", min, max))
+                        })))
+                }))?,
+            None => ctx.emit("implicits: None,")?,
+        };
+        match base.defences {
+            Some(ref d) => emit!(ctx,
+                "defences: Some(Defences{{armour: {}, evasion: {}, energy_shield: {}}}),",
+                d.armour, d.evasion, d.energy_shield)?,
+            None => ctx.emit("defences: None,")?,
+        };
+        match base.weapon {
+            Some(ref w) => emit!(ctx, "weapon: Some(BaseWeaponStats{{physical_damage: {:?}, \
+                attacks_per_second: {:?}, critical_chance: {:?}, range: {}}}),",
+                w.physical, w.attacks_per_second, w.critical_chance, w.range)?,
+            None => ctx.emit("weapon: None,")?,
+        };
+        ctx.end("},")?;
+    }
+    ctx.end("]")?;
+    Ok(())
+}
+
+/// Create Rust expression for the `ItemCategory` of items of given class.
+fn item_class_category_expr(item_class: &str) -> Option<&'static str> {
+    Some(match item_class {
+        "Amulet" => "ItemCategory::Accessory(AccessoryType::Amulet)",
+        "Belt" => "ItemCategory::Accessory(AccessoryType::Belt)",
+        "Ring" => "ItemCategory::Accessory(AccessoryType::Ring)",
+        "Helmet" => "ItemCategory::Armour(ArmourType::Helmet)",
+        "Gloves" => "ItemCategory::Armour(ArmourType::Gloves)",
+        "Body Armour" => "ItemCategory::Armour(ArmourType::Chest)",
+        "Boots" => "ItemCategory::Armour(ArmourType::Boots)",
+        "Shield" => "ItemCategory::Armour(ArmourType::Shield)",
+        "Quiver" => "ItemCategory::Armour(ArmourType::Quiver)",
+        "Bow" => "ItemCategory::Weapon(WeaponType::Bow)",
+        "Claw" => "ItemCategory::Weapon(WeaponType::Claw)",
+        "Dagger" => "ItemCategory::Weapon(WeaponType::Dagger)",
+        "One Hand Axe" => "ItemCategory::Weapon(WeaponType::OneHandedAxe)",
+        "One Hand Mace" => "ItemCategory::Weapon(WeaponType::OneHandedMace)",
+        "One Hand Sword" => "ItemCategory::Weapon(WeaponType::OneHandedSword)",
+        "Sceptre" => "ItemCategory::Weapon(WeaponType::Sceptre)",
+        "Staff" => "ItemCategory::Weapon(WeaponType::Staff)",
+        "Two Hand Axe" => "ItemCategory::Weapon(WeaponType::TwoHandedAxe)",
+        "Two Hand Mace" => "ItemCategory::Weapon(WeaponType::TwoHandedMace)",
+        "Two Hand Sword" => "ItemCategory::Weapon(WeaponType::TwoHandedSword)",
+        "Wand" => "ItemCategory::Weapon(WeaponType::Wand)",
+        "Jewel" => "ItemCategory::Jewel(JewelType::Regular)",
+        "Abyss Jewel" => "ItemCategory::Jewel(JewelType::Abyss)",
+        "Life Flask" | "Mana Flask" | "Hybrid Flask" | "Utility Flask" => "ItemCategory::Flask",
+        "Map" => "ItemCategory::Map",
+        "Map Fragment" => "ItemCategory::Fragment",
+        _ => return None,
+    })
+}
+
+/// Name of the `Requirement` variant for a requirement name used in the API.
+fn requirement_variant(name: &str) -> &str {
+    match name {
+        "Str" => "Strength",
+        "Dex" => "Dexterity",
+        "Int" => "Intelligence",
+        other => other,
+    }
+}
+
+/// Structure describing JSON objects in ITEM_BASES_JSON_FILES.
+#[derive(Debug, Default, Deserialize)]
+struct ItemBaseData {
+    name: String,
+    #[serde(rename = "class")]
+    item_class: String,
+    level: Option<u64>,
+    size: Option<(u64, u64)>,
+    #[serde(default)]
+    requirements: BTreeMap<String, u32>,
+    implicits: Option<Vec<ModStatData>>,
+    defences: Option<ItemBaseDefencesData>,
+    weapon: Option<ItemBaseWeaponData>,
+}
+#[derive(Debug, Deserialize)]
+struct ItemBaseDefencesData {
+    #[serde(default)]
+    armour: u32,
+    #[serde(default)]
+    evasion: u32,
+    #[serde(default)]
+    energy_shield: u32,
+}
+#[derive(Debug, Deserialize)]
+struct ItemBaseWeaponData {
+    physical: (u32, u32),
+    attacks_per_second: f64,
+    critical_chance: f64,
+    range: u32,
+}
+
+/// Structure describing JSON objects in MAPS_JSON_FILE.
+#[derive(Debug, Deserialize)]
+struct MapData {
+    #[serde(rename = "text")]
+    name: String,
+}
+
+
+// Skill gem handling
+
+const GEMS_JSON_FILE: &str = "data/gems.json";
+const GEM_EXPERIENCE_FILE: &str = "model/item/gem/experience.inc.rs";
+
+/// Generate code for the experience tables of skill gems.
+fn generate_gem_code() -> Result<(), Box<Error>> {
+    let file = fs::OpenOptions::new().read(true).open(Path::new(".").join(GEMS_JSON_FILE))?;
+    let gems: Vec<GemData> = serde_json::from_reader(BufReader::new(file))?;
+
+    let out = create_out_file(GEM_EXPERIENCE_FILE)?;
+    let mut ctx = codegen::Context::new(out);
+    ctx.begin("vec![")?;
+    for gem in gems {
+        emit!(ctx, "({:?}, &[{}][..]),", gem.name,
+            gem.experience.iter().format_with(", ", |(level, xp), f| {
+                f(&format_args!("({}, {})", level, xp))
+            }))?;
+    }
+    ctx.end("]")?;
+    Ok(())
+}
+
+/// Structure describing JSON objects in GEMS_JSON_FILE, as output by `data/jq/gems.jq`.
+#[derive(Debug, Deserialize)]
+struct GemData {
+    name: String,
+    /// Total experience that the gem needs to reach given level.
+    experience: BTreeMap<u32, u64>,
+}
+
 // Item mod handling
 
 const ITEM_MODS_DATA_DIR: &str = "data/mods";
-const ITEM_MODS_TYPES: &[&str] = &["crafted", "enchant", "explicit", "implicit"];
+const ITEM_MODS_TYPES: &[&str] = &[
+    "crafted", "enchant", "explicit", "implicit", "pseudo",
+];
 const ITEM_MODS_DATABASE_FILE: &str = "model/item/mods/database/by_type_and_id.inc.rs";
+const ITEM_AFFIXES_DATA_FILE: &str = "data/mods/tiers.json";
+const ITEM_AFFIXES_FILE: &str = "model/item/mods/tier/affixes.inc.rs";
 
 const READ_BUFFER_SIZE: usize = 64 * 1024;  // To speed up parsing of item mods JSON.
 
 /// Generate code for the database of `ModInfo`s.
 fn generate_item_mod_code() -> Result<(), Box<Error>> {
     generate_item_mods_mapping()?;
+    generate_item_affixes()?;
     Ok(())
 }
 
@@ -175,12 +391,13 @@ fn generate_item_mods_mapping_for_type<W: io::Write>(
     ctx.begin("{")?;
     ctx.emit("let mut hm = HashMap::new();")?;
     for imd in mods {
-        // Some mod texts contain newlines because they're used in the UI.
-        let text = imd.text.replace('\n', " ");
+        let text = imd.text.trim();
+        let mod_id = mod_id_expr(&imd.id);
+        let (key, slots) = tokenize_mod_template(text);
         ctx.begin("hm.insert(")?;
-        emit!(ctx, r#"ModId::from_str("{}").unwrap(),"#, imd.id)?;
-        emit!(ctx, r#"Arc::new(ModInfo::from_raw("{}", "{}").unwrap()),"#,
-            imd.id, text)?;
+        emit!(ctx, "{},", mod_id)?;
+        emit!(ctx, "Arc::new(ModInfo::precompiled({}, {:?}, {:?}, vec![{}])),",
+            mod_id, text, key, slots.iter().format(", "))?;
         ctx.end(");")?;
     }
     ctx.emit("hm")?;
@@ -188,6 +405,128 @@ fn generate_item_mods_mapping_for_type<W: io::Write>(
     Ok(())
 }
 
+/// Generate the list of all known `Affix`es, along with their tiers' value ranges
+/// (i.e. a `Vec<Affix>`).
+///
+/// Affixes whose stats don't correspond to any known explicit mod are skipped.
+fn generate_item_affixes() -> io::Result<()> {
+    let data_file = Path::new(".").join(ITEM_AFFIXES_DATA_FILE);
+    let file = fs::OpenOptions::new().read(true).open(data_file)?;
+    let affixes: Vec<AffixData> = serde_json::from_reader(BufReader::new(file))?;
+    let mod_ids = mod_ids_by_key("explicit")?;
+
+    let out = create_out_file(ITEM_AFFIXES_FILE)?;
+    let mut ctx = codegen::Context::new(out);
+
+    ctx.begin("vec![")?;
+    for affix in affixes {
+        let stats: Option<Vec<_>> = affix.stats.iter()
+            .map(|stat| resolve_mod_stat(stat, &mod_ids))
+            .collect();
+        let stats = match stats { Some(s) => s, None => continue };
+        ctx.begin("Affix::precompiled(")?;
+        emit!(ctx, "{:?}, {:?}, AffixType::{}, {},",
+            affix.id, affix.name, capitalize(&affix.generation), affix.level)?;
+        emit!(ctx, "vec![{}],", stats.iter().format_with(", ", |&(id, ref ranges), f| {
+            f(&format_args!("AffixStat::new({}, vec![{}])", mod_id_expr(id),
+                ranges.iter().format_with(", ", |&(min, max), f| {
+                    f(&format_args!("({:?}, {:?})", min, max))
+                })))
+        }))?;
+        emit!(ctx, "vec![{}],", affix.weights.iter().format_with(", ", |&(ref tag, w), f| {
+            f(&format_args!("({:?}, {})", tag, w))
+        }))?;
+        ctx.end("),")?;
+    }
+    ctx.end("]")?;
+    Ok(())
+}
+
+/// Words that have the opposite meaning in mod texts.
+const POLARITY_WORDS: &[(&str, &str)] = &[("increased", "reduced"), ("more", "less")];
+
+/// Find the mod that given stat (of an affix or item base) gives,
+/// and return its ID along with the value ranges of the stat.
+///
+/// The mod's template may use the opposite wording to the stat's text
+/// (e.g. "#% increased Attribute Requirements" rather than "reduced"),
+/// in which case the ranges are negated.
+///
+/// Local stats resolve to the same mod as their text on an item would,
+/// i.e. not to its "(Local)" variant, because the item texts never say so.
+fn resolve_mod_stat<'m>(stat: &ModStatData, mod_ids: &'m HashMap<String, String>)
+    -> Option<(&'m str, Vec<(f64, f64)>)>
+{
+    let (key, _) = tokenize::tokenize(stat.text.trim(), true);
+    let mut candidates = vec![(key.clone(), false)];
+    if stat.ranges.len() == 1 {
+        for &(positive, negative) in POLARITY_WORDS {
+            if key.contains(positive) {
+                candidates.push((key.replacen(positive, negative, 1), true));
+            } else if key.contains(negative) {
+                candidates.push((key.replacen(negative, positive, 1), true));
+            }
+        }
+    }
+    candidates.into_iter().filter_map(|(k, negated)| {
+        mod_ids.get(&k).map(|id| {
+            let ranges = stat.ranges.iter()
+                .map(|&(min, max)| if negated { (-max, -min) } else { (min, max) })
+                .collect();
+            (id.as_str(), ranges)
+        })
+    }).next()
+}
+
+/// Load the IDs of mods of given type, keyed by their text lookup keys.
+fn mod_ids_by_key(mod_type: &str) -> io::Result<HashMap<String, String>> {
+    let data_file = Path::new(".").join(ITEM_MODS_DATA_DIR).join(mod_type).with_extension("json");
+    let file = fs::OpenOptions::new().read(true).open(data_file)?;
+    let reader = BufReader::with_capacity(READ_BUFFER_SIZE, file);
+    let mods: Vec<ItemModData> = serde_json::from_reader(reader)?;
+
+    // Several templates may share a key, in which case pick the one
+    // that `Database::resolve` would pick for the item text: the one with most values,
+    // and then the one with the longest template.
+    let mut result: HashMap<String, ((usize, usize), String)> = HashMap::new();
+    for imd in mods {
+        let text = imd.text.trim();
+        let (key, numbers) = tokenize::tokenize(text, true);
+        let rank = (numbers.iter().filter(|n| n.digits.is_none()).count(), text.len());
+        let entry = result.entry(key).or_insert_with(|| (rank, imd.id.clone()));
+        if rank > entry.0 {
+            *entry = (rank, imd.id);
+        }
+    }
+    Ok(result.into_iter().map(|(key, (_, id))| (key, id)).collect())
+}
+
+/// Create Rust expression for the `ModId` of given mod ID string,
+/// e.g. "explicit.stat_42" or "pseudo.pseudo_total_life".
+fn mod_id_expr(id: &str) -> String {
+    let (type_, stat) = id.split_at(id.find('.').expect("mod ID with a type"));
+    let stat = &stat[1..];
+    let mod_type = format!("ModType::{}", capitalize(type_));
+    let number = if stat.starts_with("stat_") { stat[5..].parse::<u64>().ok() } else { None };
+    match number {
+        Some(n) => format!("ModId::new({}, {})", mod_type, n),
+        None => format!("ModId::with_name({}, {:?})", mod_type, stat),
+    }
+}
+
+/// Split mod text template into its lookup key and Rust expressions for its numeric slots.
+///
+/// This uses the same tokenizer as `tokenize_mod_template` in the `mods::info` module.
+fn tokenize_mod_template(text: &str) -> (String, Vec<String>) {
+    let (key, numbers) = tokenize::tokenize(text, true);
+    let slots = numbers.into_iter().map(|n| match n.digits {
+        None => format!("Slot::Value{{plus: {}}}", n.pluses > 0),
+        Some(number) => format!("Slot::Constant{{number: {:?}.into(), plus: {}}}",
+            number, n.pluses > 0),
+    }).collect();
+    (key, slots)
+}
+
 /// Structure describing the JSON objects in item mod files.
 #[derive(Debug, Deserialize)]
 struct ItemModData {
@@ -196,3 +535,20 @@ struct ItemModData {
     text: String,
     id: String,
 }
+
+/// Structure describing the JSON objects in the item affixes file.
+#[derive(Debug, Deserialize)]
+struct AffixData {
+    id: String,
+    name: String,
+    generation: String,
+    level: u64,
+    stats: Vec<ModStatData>,
+    weights: Vec<(String, u32)>,
+}
+/// Mod stat described by its text template, as output by the scripts in `data/jq`.
+#[derive(Debug, Deserialize)]
+struct ModStatData {
+    text: String,
+    ranges: Vec<(f64, f64)>,
+}
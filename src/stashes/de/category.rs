@@ -5,7 +5,7 @@ use std::fmt;
 use itertools::Itertools;
 use serde::de::{self, Deserialize, Visitor};
 
-use super::super::{AccessoryType, ArmourType, ItemCategory, JewelType, WeaponType};
+use super::super::{AccessoryType, ArmourType, ItemCategory, JewelType, WeaponType, base_info};
 
 
 const EXPECTING_MSG: &str = "item category as string or 1-element map";
@@ -21,7 +21,7 @@ const EXPECTING_MSG: &str = "item category as string or 1-element map";
 /// (i.e. {"gems": []}), so we gotta be prepared for it.
 const CATEGORIES: &[&str] = &[
     "accessories", "armour", "cards", "currency", "flasks", "gems", "jewels",
-    "maps", "weapons",
+    "leaguestones", "maps", "monsters", "weapons",
 ];
 
 
@@ -53,13 +53,13 @@ impl<'de> Visitor<'de> for ItemCategoryVisitor {
         if let Some(key) = map.next_key::<String>()? {
             let subcats: Vec<String> = map.next_value()?;
             match key.trim().to_lowercase().as_str() {
-                "accessories" => match subcats.get(0).map(|sc| sc.as_str()) {
+                "accessories" => match subcats.first().map(|sc| sc.as_str()) {
                     Some("amulet") => Ok(ItemCategory::Accessory(AccessoryType::Amulet)),
                     Some("belt") => Ok(ItemCategory::Accessory(AccessoryType::Belt)),
                     Some("ring") => Ok(ItemCategory::Accessory(AccessoryType::Ring)),
                     sc => Err(de::Error::custom(format!("unexpected accessory type: {:?}", sc))),
                 },
-                "armour" => match subcats.get(0).map(|sc| sc.as_str()) {
+                "armour" => match subcats.first().map(|sc| sc.as_str()) {
                     Some("helmet") => Ok(ItemCategory::Armour(ArmourType::Helmet)),
                     Some("gloves") => Ok(ItemCategory::Armour(ArmourType::Gloves)),
                     Some("chest") => Ok(ItemCategory::Armour(ArmourType::Chest)),
@@ -68,7 +68,7 @@ impl<'de> Visitor<'de> for ItemCategoryVisitor {
                     Some("quiver") => Ok(ItemCategory::Armour(ArmourType::Quiver)),
                     sc => Err(de::Error::custom(format!("unexpected armour type: {:?}", sc))),
                 },
-                "weapons" => match subcats.get(0).map(|sc| sc.as_str()) {
+                "weapons" => match subcats.first().map(|sc| sc.as_str()) {
                     Some("bow") => Ok(ItemCategory::Weapon(WeaponType::Bow)),
                     Some("claw") => Ok(ItemCategory::Weapon(WeaponType::Claw)),
                     Some("dagger") => Ok(ItemCategory::Weapon(WeaponType::Dagger)),
@@ -83,7 +83,7 @@ impl<'de> Visitor<'de> for ItemCategoryVisitor {
                     Some("wand") => Ok(ItemCategory::Weapon(WeaponType::Wand)),
                     sc => Err(de::Error::custom(format!("unexpected weapon type: {:?}", sc))),
                 },
-                "jewels" => match subcats.get(0).map(|sc| sc.as_str()) {
+                "jewels" => match subcats.first().map(|sc| sc.as_str()) {
                     Some("abyss") => Ok(ItemCategory::Jewel(JewelType::Abyss)),
                     None => Ok(ItemCategory::Jewel(JewelType::Regular)),  // "jewels": []
                     sc => Err(de::Error::custom(format!("unexpected jewel type: {:?}", sc))),
@@ -93,10 +93,22 @@ impl<'de> Visitor<'de> for ItemCategoryVisitor {
                 "cards" => Ok(ItemCategory::DivinationCard),
                 // N.b.: "currency" can actually also indicate a sealed prophecy item.
                 // Fortunately, this can be disambiguated using the "frameType" JSON key.
-                "currency" => Ok(ItemCategory::Currency),
+                // Other kinds of "currency" (like essences) are further refined
+                // by the item's base type (see `refine_category`).
+                "currency" => match subcats.first().map(|sc| sc.as_str()) {
+                    Some("fossil") => Ok(ItemCategory::Fossil),
+                    Some("resonator") => Ok(ItemCategory::Resonator),
+                    _ => Ok(ItemCategory::Currency),
+                },
                 "flasks" => Ok(ItemCategory::Flask),
                 "gems" => Ok(ItemCategory::Gem),
-                "maps" => Ok(ItemCategory::Map),
+                "leaguestones" => Ok(ItemCategory::Leaguestone),
+                "maps" => match subcats.first().map(|sc| sc.as_str()) {
+                    Some("fragment") => Ok(ItemCategory::Fragment),
+                    Some("scarab") => Ok(ItemCategory::Scarab),
+                    _ => Ok(ItemCategory::Map),
+                },
+                "monsters" => Ok(ItemCategory::Beast),
                 _ => Err(de::Error::unknown_field(&key, CATEGORIES)),
             }
         } else {
@@ -113,6 +125,8 @@ impl<'de> Visitor<'de> for ItemCategoryVisitor {
             "gems" => Ok(ItemCategory::Gem),
             "cards" => Ok(ItemCategory::DivinationCard),
             "currency" => Ok(ItemCategory::Currency),
+            "leaguestones" => Ok(ItemCategory::Leaguestone),
+            "monsters" => Ok(ItemCategory::Beast),
             _ => {
                 warn!("Unrecognized item category string `{}`, expected one of: {}",
                     v, CATEGORIES.iter().format(", "));
@@ -123,6 +137,38 @@ impl<'de> Visitor<'de> for ItemCategoryVisitor {
 }
 
 
+/// Refine the generic category reported by the API for an item of given base type
+/// (like `Currency` for essences, or `Map` for map fragments).
+///
+/// Known base types are looked up in the item base database,
+/// while the others are recognized by the typical wording of their names.
+pub(super) fn refine_category(category: ItemCategory, base: &str) -> ItemCategory {
+    match category {
+        ItemCategory::Currency | ItemCategory::Map => {}
+        _ => return category,
+    }
+    if let Some(info) = base_info(base) {
+        return info.category();
+    }
+    if base.contains("Essence of ") || base == "Remnant of Corruption" {
+        return ItemCategory::Essence;
+    }
+    let suffixes = &[
+        (" Fossil", ItemCategory::Fossil),
+        (" Resonator", ItemCategory::Resonator),
+        (" Scarab", ItemCategory::Scarab),
+        (" Leaguestone", ItemCategory::Leaguestone),
+        (" Net", ItemCategory::Net),
+        (" Incubator", ItemCategory::Incubator),
+        (" Oil", ItemCategory::Oil),
+    ];
+    suffixes.iter()
+        .find(|&&(suffix, _)| base.ends_with(suffix))
+        .map(|&(_, c)| c)
+        .unwrap_or(category)
+}
+
+
 #[cfg(test)]
 mod tests {
     use serde_json::from_value;
@@ -221,4 +267,33 @@ mod tests {
         assert_eq!(ItemCategory::Gem, from_value(json!({"gems": []})).unwrap());
         assert_eq!(ItemCategory::Map, from_value(json!({"maps": []})).unwrap());
     }
+
+    #[test]
+    fn league_items() {
+        assert_eq!(ItemCategory::Fossil, from_value(json!({"currency": ["fossil"]})).unwrap());
+        assert_eq!(ItemCategory::Fragment, from_value(json!({"maps": ["fragment"]})).unwrap());
+        assert_eq!(ItemCategory::Leaguestone, from_value(json!("leaguestones")).unwrap());
+        assert_eq!(ItemCategory::Beast, from_value(json!({"monsters": []})).unwrap());
+    }
+
+    #[test]
+    fn refine_category() {
+        assert_eq!(ItemCategory::Essence,
+                   super::refine_category(ItemCategory::Currency, "Essence of Greed"));
+        assert_eq!(ItemCategory::Fossil,
+                   super::refine_category(ItemCategory::Currency, "Pristine Fossil"));
+        assert_eq!(ItemCategory::Net,
+                   super::refine_category(ItemCategory::Currency, "Simple Rope Net"));
+        assert_eq!(ItemCategory::Scarab,
+                   super::refine_category(ItemCategory::Map, "Gilded Sulphite Scarab"));
+        assert_eq!(ItemCategory::Fragment,
+                   super::refine_category(ItemCategory::Map, "Mortal Hope"));
+        assert_eq!(ItemCategory::Map,
+                   super::refine_category(ItemCategory::Map, "Academy Map"));
+        assert_eq!(ItemCategory::Currency,
+                   super::refine_category(ItemCategory::Currency, "Chaos Orb"));
+        // Only the generic categories are refined.
+        assert_eq!(ItemCategory::Gem,
+                   super::refine_category(ItemCategory::Gem, "Summon Stone Golem"));
+    }
 }
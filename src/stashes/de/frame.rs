@@ -0,0 +1,49 @@
+//! Deserializer for item frame type.
+
+use std::fmt;
+
+use serde::de::{self, Deserialize, Visitor};
+
+use super::super::FrameType;
+
+
+const EXPECTING_MSG: &str = "frame type index (from 0=normal to 9=relic)";
+
+
+impl<'de> Deserialize<'de> for FrameType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: de::Deserializer<'de>
+    {
+        // Frame type is encoded in the API as the "frameType" field
+        // which is a number.
+        deserializer.deserialize_u64(FrameTypeVisitor)
+    }
+}
+
+struct FrameTypeVisitor;
+impl<'de> Visitor<'de> for FrameTypeVisitor {
+    type Value = FrameType;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}", EXPECTING_MSG)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(match v {
+            0 => FrameType::Normal,
+            1 => FrameType::Magic,
+            2 => FrameType::Rare,
+            3 => FrameType::Unique,
+            4 => FrameType::Gem,
+            5 => FrameType::Currency,
+            6 => FrameType::DivinationCard,
+            7 => FrameType::Quest,
+            8 => FrameType::Prophecy,
+            9 => FrameType::Relic,
+            _ => {
+                warn!("Unrecognized item frame type {}, expected {}", v, EXPECTING_MSG);
+                FrameType::Other(v)
+            }
+        })
+    }
+}
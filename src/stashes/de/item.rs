@@ -1,7 +1,7 @@
 //! Deserializers for item data.
 
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::time::Duration;
 
@@ -12,8 +12,11 @@ use serde_json::Value as Json;
 
 use ::common::util::Quasi;
 use super::super::{
-    Influence, Item, ItemCategory, ItemDetails, Mod, ModType, Properties, Quality, Rarity,
+    FlaskDetails, FrameType, GearDetails, GemDetails, GemTag, GemVariant, Influence, Item, ItemCategory, ItemDetails,
+    ItemSockets, MapDetails, Mod, ModType, Properties, Quality, Rarity, gem_level_cap,
+    split_magic_name,
 };
+use super::category::refine_category;
 use super::util::deserialize;
 
 
@@ -47,30 +50,37 @@ impl<'de> Visitor<'de> for ItemVisitor {
         let mut base = None;
         let mut level = None;
         let mut category = None;
-        let mut rarity = None;
+        let mut frame_type = None;
         let mut quality = None;
         let mut properties = None;
         let mut identified = None;
         let mut sockets = None;
+        let mut socketed_items = None;
         let mut requirements = None;
         let mut corrupted = None;
-        let mut influence = None;
+        let (mut elder, mut shaper, mut influences) = (None, None, None);
         let mut duplicated = None;
+        let (mut fractured, mut synthesised) = (None, None);
         let mut flavour_text = None;
+        let (mut stack_size, mut max_stack_size) = (None, None);
         let mut extra = HashMap::new();
         // Data specific to a particular category of items.
         let (mut map_tier,
              mut item_quantity,
              mut item_rarity,
              mut monster_pack_size) = (None, None, None, None);
-        let (mut gem_level, mut gem_xp) = (None, None);
+        let (mut gem_level, mut gem_max_level, mut gem_xp) = (None, false, None);
+        let (mut gem_tags, mut gem_support) = (None, None);
         let (mut duration,
              mut charges_per_use,
-             mut flask_mods) = (None, None, None);
+             mut max_charges,
+             mut flask_mods) = (None, None, None, None);
         let (mut implicit_mods,
              mut enchant_mods,
+             mut fractured_mods,
              mut explicit_mods,
-             mut crafted_mods) = (None, None, None, None);
+             mut veiled_mods,
+             mut crafted_mods) = (None, None, None, None, None, None);
 
         while let Some(key) = map.next_key::<String>()? {
             let key = key.trim();
@@ -96,6 +106,14 @@ impl<'de> Visitor<'de> for ItemVisitor {
                     check_duplicate!("ilvl" => level);
                     level = Some(map.next_value()?);
                 }
+                "stackSize" => {
+                    check_duplicate!("stackSize" => stack_size);
+                    stack_size = Some(map.next_value()?);
+                }
+                "maxStackSize" => {
+                    check_duplicate!("maxStackSize" => max_stack_size);
+                    max_stack_size = Some(map.next_value()?);
+                }
                 "requirements" => {
                     check_duplicate!(requirements);
 
@@ -119,31 +137,13 @@ impl<'de> Visitor<'de> for ItemVisitor {
 
                 // Item category / type.
                 "frameType" => {
-                    check_duplicate!("frameType" => rarity);
-
                     // This field is wonky, as it describes either an item rarity,
                     // or a less common item category (like a sealed prophecy item or a divination card).
-                    const MAX_RARITY: u64 = 3;  // corresponds to Rarity::Unique
-                    let value: u64 = map.next_value()?;
-                    if value > MAX_RARITY {
-                        // If the frameType doesn't describe rarity but rather item type,
-                        // default to normal rarity.
-                        rarity = Some(Rarity::Normal);
-                        // Detect the uncommon item types.
-                        match value {
-                            8 => { category = Some(Quasi::from(ItemCategory::Prophecy)); }
-                            _ => {}
-                        }
-                    } else {
-                        rarity = Some(deserialize(value)?);
-                    }
+                    // Both are derived from it once all the item attributes are known.
+                    check_duplicate!("frameType" => frame_type);
+                    frame_type = Some(map.next_value()?);
                 }
                 "category" => {
-                    if category.as_ref().and_then(|c| c.as_ref()) == Some(&ItemCategory::Prophecy) {
-                        // Sealed prophecies are detected using the "frameType" attribute,
-                        // because their JSON "category" is actually `{"currency": []}` (!).
-                        continue;
-                    }
                     check_duplicate!(category);
                     category = Some(map.next_value::<Quasi<ItemCategory>>()?);
                 }
@@ -159,16 +159,25 @@ impl<'de> Visitor<'de> for ItemVisitor {
                 }
                 "implicitMods" => {
                     check_duplicate!("implicitMods" => implicit_mods);
-                    implicit_mods = Some(Self::deserialize_mods(&mut map, ModType::Implicit)?);
+                    // Mods are created once we know whether the item is synthesised.
+                    implicit_mods = Some(map.next_value::<Vec<String>>()?);
                 }
                 "enchantMods" => {
                     check_duplicate!("enchantMods" => enchant_mods);
                     enchant_mods = Some(Self::deserialize_mods(&mut map, ModType::Enchant)?);
                 }
+                "fracturedMods" => {
+                    check_duplicate!("fracturedMods" => fractured_mods);
+                    fractured_mods = Some(Self::deserialize_mods(&mut map, ModType::Fractured)?);
+                }
                 "explicitMods" => {
                     check_duplicate!("explicitMods" => explicit_mods);
                     explicit_mods = Some(Self::deserialize_mods(&mut map, ModType::Explicit)?);
                 }
+                "veiledMods" => {
+                    check_duplicate!("veiledMods" => veiled_mods);
+                    veiled_mods = Some(Self::deserialize_mods(&mut map, ModType::Veiled)?);
+                }
                 "craftedMods" => {
                     check_duplicate!("craftedMods" => crafted_mods);
                     crafted_mods = Some(Self::deserialize_mods(&mut map, ModType::Crafted)?);
@@ -180,13 +189,8 @@ impl<'de> Visitor<'de> for ItemVisitor {
                     sockets = Some(map.next_value()?);
                 }
                 "socketedItems" => {
-                    // These are unsupported and ignored for now.
-                    let socketed: Vec<Json> = map.next_value()?;
-                    if !socketed.is_empty() {
-                        warn!("Ignoring non-empty `socketedItems` in {}", name.as_ref()
-                            .and_then(|n| n.as_ref().map(|n| n.as_str()))
-                            .unwrap_or("<unknown item>"));
-                    }
+                    check_duplicate!("socketedItems" => socketed_items);
+                    socketed_items = Some(Self::deserialize_socketed_items(&mut map)?);
                 }
 
                 // Overall item modifiers.
@@ -198,19 +202,27 @@ impl<'de> Visitor<'de> for ItemVisitor {
                     check_duplicate!(duplicated);  // yo dawg
                     duplicated = Some(map.next_value()?);
                 }
+                "fractured" => {
+                    check_duplicate!(fractured);
+                    fractured = Some(map.next_value()?);
+                }
+                "synthesised" => {
+                    check_duplicate!(synthesised);
+                    synthesised = Some(map.next_value()?);
+                }
+                // Legacy flags for Elder & Shaper influence
+                // (which may also be repeated in the "influences" map).
                 "elder" => {
-                    check_duplicate!("elder/shaper" => influence);
-                    let is_elder = map.next_value()?;
-                    if is_elder {
-                        influence = Some(Some(Influence::Elder));
-                    }
+                    check_duplicate!(elder);
+                    elder = Some(map.next_value()?);
                 }
                 "shaper" => {
-                    check_duplicate!("elder/shaper" => influence);
-                    let is_shaped = map.next_value()?;
-                    if is_shaped {
-                        influence = Some(Some(Influence::Shaper));
-                    }
+                    check_duplicate!(shaper);
+                    shaper = Some(map.next_value()?);
+                }
+                "influences" => {
+                    check_duplicate!(influences);
+                    influences = Some(Self::deserialize_influences(&mut map)?);
                 }
 
                 // Various other properties.
@@ -280,13 +292,23 @@ impl<'de> Visitor<'de> for ItemVisitor {
                             return Err(de::Error::duplicate_field("Level"));
                         }
                         // For max-level gems, level is given as something like "20 (Max)".
-                        // TODO: add is_max_level flag to ItemDetails::Gem,
-                        // or make a dedicated data type for Level that includes this bit
+                        gem_max_level = lvl.contains("(Max)");
                         lvl = lvl.replace("(Max)", "").trim().to_owned();
                         gem_level = Some(lvl.parse().map_err(|_| {
                             de::Error::invalid_value(Unexpected::Str(&lvl), &"number as string")
                         })?);
                     }
+                    if gem_level.is_some() {
+                        // Gem tags (like "Spell" or "Support") are given as value-less properties.
+                        let tags: HashSet<GemTag> = props.iter()
+                            .filter(|&(_, v)| v.is_none())
+                            .filter_map(|(k, _)| k.parse().ok())
+                            .collect();
+                        for tag in &tags {
+                            props.remove(tag.as_str());
+                        }
+                        gem_tags = Some(tags);
+                    }
 
                     // Flask properties.
                     if let Some(secs) = props.remove("Lasts %0 Seconds") {
@@ -294,20 +316,31 @@ impl<'de> Visitor<'de> for ItemVisitor {
                         if duration.is_some() {
                             return Err(de::Error::duplicate_field("Lasts %0 Seconds"));
                         }
-                        let secs: f64 = secs.parse().map_err(|_| {
-                            de::Error::invalid_value(Unexpected::Str(&secs), &"flask duration in seconds")
-                        })?;
-                        duration = Some(Duration::from_millis((secs * 1000.0) as u64));
+                        duration = Some(parse_flask_duration(&secs)?);
                     }
-                    if let Some(charges) = props.remove("Consumes %0 of %1 Charges on use") {
-                        // TODO: support the other value, i.e. max_charges
-                        let charges = charges.expect("number of flask charges per use");
+                    // Life & mana flasks don't have a separate duration property,
+                    // but rather report it along with the amount they recover,
+                    // e.g. "Recovers %0 Life over %1 Seconds".
+                    // (The property itself is retained, as the amount isn't stored elsewhere).
+                    if duration.is_none() {
+                        let secs = props.keys()
+                            .filter(|k| k.starts_with("Recovers ") && k.ends_with(" Seconds"))
+                            .filter_map(|k| props.get_values(k.as_str()).and_then(|vs| vs.last()))
+                            .next();
+                        if let Some(secs) = secs {
+                            duration = Some(parse_flask_duration(secs)?);
+                        }
+                    }
+                    if let Some(charges) = props.remove_values("Consumes %0 of %1 Charges on use") {
                         if charges_per_use.is_some() {
                             return Err(de::Error::duplicate_field("Consumes %0 of %1 Charges on use"));
                         }
-                        charges_per_use = Some(charges.parse().map_err(|_| {
-                            de::Error::invalid_value(Unexpected::Str(&charges), &"number as string")
-                        })?);
+                        let mut charges = charges.into_iter().map(|c| c.parse().map_err(|_| {
+                            de::Error::invalid_value(Unexpected::Str(&c), &"number as string")
+                        }));
+                        charges_per_use = Some(charges.next()
+                            .expect("number of flask charges per use")?);
+                        max_charges = charges.next().map_or(Ok(None), |c| c.map(Some))?;
                     }
 
                     properties = Some(props);
@@ -334,7 +367,10 @@ impl<'de> Visitor<'de> for ItemVisitor {
                 // Ignored / unrecognized fields.
                 "nextLevelRequirements" => { map.next_value::<Json>()?; },  // ignore for now
                 "verified" => { map.next_value::<bool>()?; }  // ignore
-                "support" => { map.next_value::<bool>()?; }   // ignore
+                "support" => {
+                    check_duplicate!("support" => gem_support);
+                    gem_support = Some(map.next_value::<bool>()?);
+                }
                 "league" => { map.next_value::<String>()?; }  // ignore, handled by `Stash`
                 "lockedToCharacter" => { map.next_value::<bool>()?; }  // ignore
                 key => {
@@ -348,26 +384,69 @@ impl<'de> Visitor<'de> for ItemVisitor {
         let mut name = name.ok_or_else(|| de::Error::missing_field("name"))?;
         let mut base = base.ok_or_else(|| de::Error::missing_field("typeLine"))?;
         let level = level.ok_or_else(|| de::Error::missing_field("ilvl"))?;
-        let category = category.ok_or_else(|| de::Error::missing_field("category"))?;
-        let rarity = rarity.ok_or_else(|| de::Error::missing_field("frameType"))?;
+        let frame_type: FrameType = frame_type.ok_or_else(|| de::Error::missing_field("frameType"))?;
+        let rarity = frame_type.rarity();
+        if let Some(cat) = category.as_ref().and_then(|c| c.as_ref()) {
+            if !frame_type.admits_category(cat) {
+                warn!("Item category {:?} doesn't match its frame type {:?}", cat, frame_type);
+            }
+        }
+        // Some item kinds are only detectable through their frame type,
+        // e.g. sealed prophecies whose JSON "category" is actually `{"currency": []}` (!),
+        // or relics which are reported under the category of their base item.
+        // Otherwise, the frame type is only used if the category is missing altogether.
+        let category = match (frame_type, category) {
+            (FrameType::Prophecy, _) => Quasi::from(ItemCategory::Prophecy),
+            (FrameType::Relic, _) => Quasi::from(ItemCategory::Relic),
+            (_, Some(category)) => category,
+            (frame_type, None) => frame_type.category().map(Quasi::from)
+                .ok_or_else(|| de::Error::missing_field("category"))?,
+        };
+        let category = match category {
+            Quasi::True(category) => Quasi::from(refine_category(category, &base)),
+            category => category,
+        };
         let quality = quality.unwrap_or_default();
+        let stack_size = stack_size.unwrap_or(1);
         let properties = properties.unwrap_or_default();
-        let sockets = sockets.unwrap_or_default();
+        let mut sockets: ItemSockets = sockets.unwrap_or_default();
+        for (socket, item) in socketed_items.unwrap_or_default() {
+            match sockets.put_item(socket, item) {
+                Ok(None) => {}
+                Ok(Some(_)) => return Err(de::Error::custom(format!(
+                    "multiple socketed items in socket #{}", socket))),
+                Err(_) => return Err(de::Error::custom(format!(
+                    "socketed item in a non-existent socket #{} (out of {})",
+                    socket, sockets.count()))),
+            }
+        }
         let requirements = requirements.unwrap_or_default();
         let corrupted = corrupted.unwrap_or(false);
-        let influence = influence.unwrap_or(None);
+        let mut influences = influences.unwrap_or_default();
+        if elder.unwrap_or(false) {
+            influences.insert(Influence::Elder);
+        }
+        if shaper.unwrap_or(false) {
+            influences.insert(Influence::Shaper);
+        }
         let duplicated = duplicated.unwrap_or(false);
+        let fractured = fractured.unwrap_or(false);
+        let synthesised = synthesised.unwrap_or(false);
+        // Implicits of synthesised items come from synthesis rather than the item base,
+        // but the API only tells them apart through the "synthesised" flag.
+        let implicit_mods = implicit_mods.map(|mods| {
+            let mod_type = if synthesised { ModType::Synthesised } else { ModType::Implicit };
+            Mod::from_lines(mod_type, mods)
+        });
         let flavour_text = flavour_text.unwrap_or(None);
 
         // Also, if a magic item has no name by itself,
-        // then its "typeLine" (i.e. `base`) is what actually contains its full name.
+        // then its "typeLine" (i.e. `base`) is what actually contains its full name,
+        // including the names of its prefix and suffix.
         if rarity == Rarity::Magic && name.is_none() && !base.is_empty() {
             name = Some(base.to_owned());
-            // TODO: Figure out a way of determining what the prefix and suffix is,
-            // and eliminating them from `name` to get the base name.
-            // Right now, unfortunately, the API doesn't return the affixes, just mods,
-            // which means we cannot tell anything with certainty due to hybrid affixes.
-            base = "".into();
+            // If the base type cannot be determined, leave it empty rather than wrong.
+            base = split_magic_name(&base).map(|(_, b, _)| b.to_owned()).unwrap_or_default();
         }
 
         // Round up most of the item information into an ItemDetails data type.
@@ -375,40 +454,55 @@ impl<'de> Visitor<'de> for ItemVisitor {
             let identified = identified.unwrap_or(true);
             let has_gear_mods =
                 implicit_mods.is_some() || enchant_mods.is_some()
-                || explicit_mods.is_some() || crafted_mods.is_some();
+                || fractured_mods.is_some() || explicit_mods.is_some()
+                || veiled_mods.is_some() || crafted_mods.is_some();
 
             // TODO: verify that we didn't get an invalid combination of fields
             // (like flask_mods + crafted_mods)
             if !identified {
                 Some(ItemDetails::Unidentified)
             } else if let Some(tier) = map_tier {
-                Some(ItemDetails::Map{
+                Some(ItemDetails::Map(MapDetails{
                     tier: tier,
                     item_quantity: item_quantity.unwrap_or(0),
                     item_rarity: item_rarity.unwrap_or(0),
                     monster_pack_size: monster_pack_size.unwrap_or(0),
                     mods: explicit_mods.unwrap_or_default(),
-                })
+                }))
             } else if let (Some(level), Some(xp)) = (gem_level, gem_xp) {
-                Some(ItemDetails::Gem{
+                let tags = gem_tags.unwrap_or_default();
+                // Fall back to the gem tags if the API didn't tell us whether it's a support gem.
+                let support = gem_support.unwrap_or_else(|| tags.contains(&GemTag::Support));
+                Some(ItemDetails::Gem(GemDetails{
                     level,
+                    max_level: gem_max_level,
+                    level_cap: gem_level_cap(&base),
                     experience: xp,
-                })
-            } else if let (Some(duration),
-                           Some(charges_per_use),
-                           Some(mods)) = (duration, charges_per_use, flask_mods) {
-                Some(ItemDetails::Flask{duration, charges_per_use, mods})
+                    tags,
+                    support,
+                    variant: GemVariant::of(&base),
+                }))
+            } else if let (Some(duration), Some(charges_per_use)) = (duration, charges_per_use) {
+                // Only utility flasks have utility mods, but explicit mods of any magic
+                // & unique flasks (e.g. charge recovery) also belong to the flask.
+                let mut mods = flask_mods.unwrap_or_default();
+                mods.extend(explicit_mods.unwrap_or_default());
+                Some(ItemDetails::Flask(FlaskDetails{duration, charges_per_use, max_charges, mods}))
             } else if has_gear_mods
-                      // Exclude currencies here because the API returns their on-use "mods"
+                      // Exclude currencies (and similar items, like essences) here
+                      // because the API returns their on-use "mods"
                       // (like "Reforges a rare item with new random properties" for Chaos Orb)
                       // as `explicitMods`, and they obviously aren't `Gear`.
-                      && category.as_ref() != Some(&ItemCategory::Currency) {
-                Some(ItemDetails::Gear{
+                      && category.as_ref().map(|c| !c.is_consumable() || c.is_modifiable())
+                          .unwrap_or(true) {
+                Some(ItemDetails::Gear(GearDetails{
                     implicit: implicit_mods.unwrap_or_default(),
                     enchants: enchant_mods.unwrap_or_default(),
+                    fractured: fractured_mods.unwrap_or_default(),
                     explicit: explicit_mods.unwrap_or_default(),
+                    veiled: veiled_mods.unwrap_or_default(),
                     crafted: crafted_mods.unwrap_or_default(),
-                })
+                }))
             } else {
                 // Some items -- like currencies or Sacrifice at Noon/Dusk/etc. fragments
                 // -- don't have any identifiable details.
@@ -420,9 +514,9 @@ impl<'de> Visitor<'de> for ItemVisitor {
             id,
             name: name.map(|n| n.to_string()),
             base: base.to_string(),
-            level, category, rarity, quality, properties, details,
-            sockets, requirements, corrupted, influence, duplicated, flavour_text,
-            extra,
+            level, category, rarity, frame_type, quality, properties, details,
+            sockets, requirements, corrupted, influences, duplicated,
+            fractured, synthesised, flavour_text, stack_size, max_stack_size, extra,
         })
     }
 }
@@ -438,13 +532,56 @@ impl ItemVisitor {
         })
     }
 
+    /// Deserialize items socketed into an item, together with their socket indices.
+    fn deserialize_socketed_items<'de, V>(map: &mut V) -> Result<Vec<(usize, Item)>, V::Error>
+        where V: de::MapAccess<'de>
+    {
+        let items_json: Vec<HashMap<String, ::common::util::Json>> = map.next_value()?;
+        let mut result = Vec::with_capacity(items_json.len());
+        for mut item_json in items_json {
+            // Besides the regular item data, socketed items have the index of their socket
+            // and its color (which we already know from "sockets" of the parent item).
+            let socket = item_json.remove("socket").and_then(|s| s.as_u64())
+                .ok_or_else(|| de::Error::missing_field("socket"))?;
+            item_json.remove("colour");
+            let item = deserialize(item_json).map_err(|e: de::value::Error| {
+                de::Error::custom(format!("cannot deserialize socketed item: {}", e))
+            })?;
+            result.push((socket as usize, item));
+        }
+        Ok(result)
+    }
+
+    /// Deserialize the map of Atlas influences of an item
+    /// (e.g. `{"crusader": true, "hunter": true}`) into a set.
+    ///
+    /// Unrecognized influences are skipped.
+    fn deserialize_influences<'de, V>(map: &mut V) -> Result<HashSet<Influence>, V::Error>
+        where V: de::MapAccess<'de>
+    {
+        let flags: HashMap<String, bool> = map.next_value()?;
+        let mut result = HashSet::with_capacity(flags.len());
+        for (name, flag) in flags {
+            let influence = match name.parse() {
+                Ok(influence) => influence,
+                Err(_) => {
+                    warn!("Unrecognized item influence `{}`, expected one of: {}",
+                        name, Influence::iter_variants().map(|i| i.as_str()).format(", "));
+                    continue;
+                }
+            };
+            if flag {
+                result.insert(influence);
+            }
+        }
+        Ok(result)
+    }
+
     /// Deserialize a collection of item mods of given type.
     fn deserialize_mods<'de, V>(map: &mut V, mod_type: ModType) -> Result<Vec<Mod>, V::Error>
         where V: de::MapAccess<'de>
     {
-        map.next_value().map(|mods: Vec<String>| {
-            mods.into_iter().map(|m| Mod::new(mod_type, m)).collect()
-        })
+        map.next_value().map(|mods: Vec<String>| Mod::from_lines(mod_type, mods))
     }
 }
 impl ItemVisitor {
@@ -474,11 +611,23 @@ fn remove_angle_bracket_tags(s: &str) -> Cow<str> {
     ANGLE_TAG_RE.replace_all(s, "")
 }
 
+/// Parse the duration of a flask (effect or recovery) given in seconds, like "4.00".
+fn parse_flask_duration<E: de::Error>(secs: &str) -> Result<Duration, E> {
+    let secs: f64 = secs.parse().map_err(|_| {
+        de::Error::invalid_value(Unexpected::Str(secs), &"flask duration in seconds")
+    })?;
+    Ok(Duration::from_millis((secs * 1000.0) as u64))
+}
+
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
     use serde_json::from_value;
-    use ::stashes::Item;
+    use ::stashes::{
+        ArmourType, Currency, FrameType, GemTag, GemVariant, Influence, Item, ItemCategory,
+        ModType, Rarity, SocketPosition,
+    };
 
     #[test]
     fn minimal() {
@@ -493,6 +642,125 @@ mod tests {
         from_value::<Item>(item_spec).unwrap();
     }
 
+    #[test]
+    fn magic_item_name() {
+        let item_spec = json!({
+            "id": "123abc",
+            "name": "",
+            "typeLine": "<<set:MS>><<set:M>><<set:S>>Virile Vaal Regalia of the Titan",
+            "ilvl": 80,
+            "category": {"armour": ["chest"]},
+            "frameType": 1,
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        assert_eq!(Some("Virile Vaal Regalia of the Titan"), item.name.as_ref().map(|n| n.as_str()));
+        assert_eq!("Vaal Regalia", item.base);
+        assert_eq!(Some("Virile"), item.name_prefix());
+        assert_eq!(Some("of the Titan"), item.name_suffix());
+    }
+
+    #[test]
+    fn with_socketed_items() {
+        // Taken from testdata/public-stash-tabs.json (and abridged).
+        let item_spec = json!({
+            "id": "3feaa536e6710f326cde7880cb44d184803d48f5f1562fa79498506f1199a54b",
+            "name": "<<set:MS>><<set:M>><<set:S>>Rise of the Phoenix",
+            "typeLine": "Mosaic Kite Shield",
+            "ilvl": 67,
+            "frameType": 3,
+            "category": {"armour": ["shield"]},
+            "sockets": [
+                {"group": 0, "attr": "S", "sColour": "R"},
+                {"group": 0, "attr": "S", "sColour": "R"},
+                {"group": 0, "attr": "I", "sColour": "B"},
+            ],
+            "socketedItems": [
+                {
+                    "id": "e7e027943c35863aee069050f8d21b84d573bb558f2884143a7a8c4a087ff026",
+                    "name": "",
+                    "typeLine": "Enduring Cry",
+                    "ilvl": 0,
+                    "support": false,
+                    "frameType": 4,
+                    "category": "gems",
+                    "socket": 1,
+                    "colour": "S",
+                },
+            ],
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        assert_eq!(3, item.sockets.max_links());
+
+        let socketed: Vec<_> = item.sockets.socketed_items().collect();
+        assert_eq!(1, socketed.len());
+        assert_eq!(1, socketed[0].socket);
+        assert_eq!(SocketPosition::Regular{group: 0, index: 1}, socketed[0].position);
+        assert_eq!("Enduring Cry", socketed[0].item.base);
+
+        let group = item.sockets.groups().next().unwrap();
+        assert_eq!(vec![false, true, false],
+            group.items().map(|i| i.is_some()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn with_abyss_jewel() {
+        let item_spec = json!({
+            "id": "123abc",
+            "name": "Example Belt",
+            "typeLine": "Stygian Vise",
+            "ilvl": 80,
+            "frameType": 2,
+            "category": {"accessories": ["belt"]},
+            "sockets": [{"group": 0, "attr": "A", "sColour": "A"}],
+            "socketedItems": [
+                {
+                    "id": "456def",
+                    "name": "Example Eye",
+                    "typeLine": "Searching Eye Jewel",
+                    "ilvl": 75,
+                    "frameType": 2,
+                    "category": {"jewels": ["abyss"]},
+                    "abyssJewel": true,
+                    "socket": 0,
+                    "colour": "A",
+                },
+            ],
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        assert_eq!(1, item.sockets.abyssal_count());
+        assert_eq!(0, item.sockets.regular_count());
+
+        let jewels: Vec<_> = item.sockets.abyssal_items().collect();
+        assert_eq!(1, jewels.len());
+        assert_eq!("Searching Eye Jewel", jewels[0].base);
+        assert_eq!(Some(SocketPosition::Abyssal(0)), item.sockets.position(0));
+    }
+
+    #[test]
+    fn socketed_item_in_nonexistent_socket() {
+        let item_spec = json!({
+            "id": "123abc",
+            "name": "",
+            "typeLine": "Iron Ring",
+            "ilvl": 80,
+            "frameType": 0,
+            "category": {"accessories": ["ring"]},
+            "socketedItems": [
+                {
+                    "id": "456def",
+                    "name": "",
+                    "typeLine": "Fireball",
+                    "ilvl": 0,
+                    "frameType": 4,
+                    "category": "gems",
+                    "socket": 0,
+                    "colour": "I",
+                },
+            ],
+        });
+        assert!(from_value::<Item>(item_spec).is_err());
+    }
+
     #[test]
     fn with_quality() {
         let item_spec = json!({
@@ -513,4 +781,268 @@ mod tests {
         let quality: u8 = item.quality.into();
         assert_eq!(13, quality);
     }
+
+    #[test]
+    fn flask_details() {
+        let item_spec = json!({
+            "id": "123abc",
+            "name": "",
+            "typeLine": "Quicksilver Flask",
+            "ilvl": 80,
+            "category": "flasks",
+            "frameType": 0,
+            "properties": [
+                {"name": "Lasts %0 Seconds", "values": [["4.00", 0]]},
+                {"name": "Consumes %0 of %1 Charges on use", "values": [["30", 0], ["50", 0]]},
+            ],
+            "utilityMods": ["40% increased Movement Speed"],
+            "explicitMods": ["46% increased Charge Recovery"],
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        let flask = item.as_flask().unwrap();
+        assert_eq!(30, flask.charges_per_use);
+        assert_eq!(Some(50), flask.max_charges);
+        assert_eq!(Some(1), flask.uses());
+        assert_eq!(2, flask.mods.len());
+        assert_eq!(46, flask.charge_recovery());
+        assert!(item.as_gear().is_none());
+    }
+
+    #[test]
+    fn life_flask_details() {
+        let item_spec = json!({
+            "id": "123abc",
+            "name": "",
+            "typeLine": "Seething Divine Life Flask of Staunching",
+            "ilvl": 80,
+            "category": "flasks",
+            "frameType": 1,
+            "properties": [
+                {"name": "Recovers %0 Life over %1 Seconds", "values": [["2400", 0], ["1.00", 0]]},
+                {"name": "Consumes %0 of %1 Charges on use", "values": [["15", 0], ["45", 0]]},
+            ],
+            "explicitMods": [
+                "Instant Recovery when on Low Life",
+                "Grants Immunity to Bleeding for 4 seconds if used while Bleeding",
+            ],
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        let flask = item.as_flask().unwrap();
+        assert_eq!(Duration::from_secs(1), flask.duration);
+        assert_eq!(Some(3), flask.uses());
+        assert_eq!(2, flask.mods.len());
+        assert!(item.properties.contains("Recovers %0 Life over %1 Seconds"));
+    }
+
+    #[test]
+    fn fractured_and_veiled_mods() {
+        let item_spec = json!({
+            "id": "123abc",
+            "name": "Dusk Grasp",
+            "typeLine": "Vaal Gauntlets",
+            "ilvl": 84,
+            "category": {"armour": ["gloves"]},
+            "frameType": 2,
+            "fractured": true,
+            "synthesised": false,
+            "fracturedMods": ["+42 to maximum Life"],
+            "explicitMods": ["+30% to Fire Resistance"],
+            "veiledMods": ["Veiled Suffix"],
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        assert!(item.fractured);
+        assert!(!item.synthesised);
+        let gear = item.as_gear().unwrap();
+        assert!(gear.is_fractured() && gear.is_veiled());
+        assert_eq!(ModType::Fractured, gear.fractured[0].mod_type());
+        assert_eq!(ModType::Veiled, gear.veiled[0].mod_type());
+        assert_eq!(2, gear.explicit_mods().count());
+        assert_eq!(3, gear.mods().count());
+    }
+
+    #[test]
+    fn synthesised_implicits() {
+        let item_spec = json!({
+            "id": "123abc",
+            "name": "Doom Loop",
+            "typeLine": "Synthesised Iron Ring",
+            "ilvl": 84,
+            "category": {"accessories": ["ring"]},
+            "frameType": 2,
+            "synthesised": true,
+            "implicitMods": ["+22% to Cold Resistance"],
+            "explicitMods": ["+30% to Fire Resistance"],
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        assert!(item.synthesised);
+        let gear = item.as_gear().unwrap();
+        assert_eq!(ModType::Synthesised, gear.implicit[0].mod_type());
+    }
+
+    #[test]
+    fn influences() {
+        let item_spec = json!({
+            "id": "123abc",
+            "name": "Foo Bar",
+            "typeLine": "Vaal Regalia",
+            "ilvl": 86,
+            "category": {"armour": ["chest"]},
+            "frameType": 2,
+            "shaper": true,
+            "influences": {"shaper": true, "warlord": true, "eater": true},
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        // Unknown influences are ignored.
+        assert_eq!(2, item.influences.len());
+        assert!(item.is_shaped() && !item.is_elder());
+        assert!(item.has_influence(Influence::Warlord));
+        assert!(item.is_conqueror_influenced());
+    }
+
+    #[test]
+    fn legacy_influence() {
+        let item_spec = json!({
+            "id": "123abc",
+            "name": "Foo Bar",
+            "typeLine": "Vaal Regalia",
+            "ilvl": 86,
+            "category": {"armour": ["chest"]},
+            "frameType": 2,
+            "elder": true,
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        assert!(item.is_elder());
+        assert!(!item.is_conqueror_influenced());
+    }
+
+    #[test]
+    fn gem_details() {
+        let item_spec = json!({
+            "id": "123abc",
+            "name": "",
+            "typeLine": "Cleave",
+            "ilvl": 0,
+            "category": "gems",
+            "frameType": 4,
+            "properties": [{"name": "Level", "values": [["20 (Max)", 0]]}],
+            "additionalProperties": [{"name": "Experience", "values": [["1/1", 0]]}],
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        let gem = item.as_gem().unwrap();
+        assert_eq!(20, gem.level);
+        assert!(gem.is_max_level());
+        assert!(!gem.is_overleveled());
+    }
+
+    #[test]
+    fn gem_tags() {
+        let item_spec = json!({
+            "id": "123abc",
+            "name": "",
+            "typeLine": "Vaal Fireball",
+            "ilvl": 0,
+            "category": "gems",
+            "frameType": 4,
+            "support": false,
+            "corrupted": true,
+            "properties": [
+                {"name": "Vaal, Spell, AoE, Projectile, Fire", "values": []},
+                {"name": "Level", "values": [["21 (Max)", 0]]},
+            ],
+            "additionalProperties": [{"name": "Experience", "values": [["1/1", 0]]}],
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        let gem = item.as_gem().unwrap();
+        assert!(gem.is_active());
+        assert!(gem.has_tag(GemTag::Spell) && gem.has_tag(GemTag::Fire));
+        assert_eq!(5, gem.tags.len());
+        assert_eq!(GemVariant::Vaal, gem.variant);
+        assert!(gem.is_overleveled());
+        assert!(item.properties.is_empty());
+    }
+
+    #[test]
+    fn relic_frame_type() {
+        let item_spec = json!({
+            "id": "123abc",
+            "name": "Kaom's Heart",
+            "typeLine": "Glorious Plate",
+            "ilvl": 80,
+            "category": {"armour": ["chest"]},
+            "frameType": 9,
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        assert_eq!(FrameType::Relic, item.frame_type);
+        assert_eq!(Rarity::Unique, item.rarity);
+        assert_eq!(Some(&ItemCategory::Relic), item.category.as_ref());
+        assert_eq!(Some(ItemCategory::Armour(ArmourType::Chest)),
+                   item.base_info().map(|b| b.category()));
+    }
+
+    #[test]
+    fn prophecy_frame_type() {
+        let item_spec = json!({
+            "id": "123abc",
+            "name": "",
+            "typeLine": "The Nest",
+            "ilvl": 0,
+            "category": {"currency": []},
+            "frameType": 8,
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        assert_eq!(FrameType::Prophecy, item.frame_type);
+        assert_eq!(Some(&ItemCategory::Prophecy), item.category.as_ref());
+    }
+
+    #[test]
+    fn unknown_frame_type() {
+        let item_spec = json!({
+            "id": "123abc",
+            "name": "",
+            "typeLine": "Astral Plate",
+            "ilvl": 84,
+            "category": {"armour": ["chest"]},
+            "frameType": 42,
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        assert_eq!(FrameType::Other(42), item.frame_type);
+        assert_eq!(Some(&ItemCategory::Armour(ArmourType::Chest)), item.category.as_ref());
+    }
+
+    #[test]
+    fn essence_category() {
+        let item_spec = json!({
+            "id": "123abc",
+            "name": "",
+            "typeLine": "Screaming Essence of Greed",
+            "ilvl": 0,
+            "category": "currency",
+            "frameType": 5,
+            "explicitMods": ["Upgrades a normal item to rare with one guaranteed property"],
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        assert_eq!(Some(&ItemCategory::Essence), item.category.as_ref());
+        assert!(item.details.is_none());
+    }
+
+    #[test]
+    fn currency_stack() {
+        let item_spec = json!({
+            "id": "123abc",
+            "name": "",
+            "typeLine": "Chaos Orb",
+            "ilvl": 0,
+            "category": "currency",
+            "frameType": 5,
+            "stackSize": 7,
+            "maxStackSize": 10,
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        assert_eq!(7, item.stack_size);
+        assert_eq!(Some(10), item.max_stack_size);
+        assert_eq!(7, item.stack_size());
+        assert_eq!(Some(10), item.max_stack_size());
+        assert_eq!(Some(Currency::ChaosOrb), item.as_currency());
+        assert!(!item.extra.contains_key("stackSize"));
+    }
 }
@@ -6,6 +6,7 @@ mod util;
 mod category;
 mod currency;
 mod experience;
+mod frame;
 mod item;
 mod label;
 mod price;
@@ -55,26 +55,26 @@ impl<'de> Visitor<'de> for PropertiesVisitor {
                 .and_then(|n| n.as_str().map(|s| s.to_owned()))
                 .ok_or_else(|| de::Error::missing_field("name"))?;
             let values = prop.get("values").ok_or_else(|| de::Error::missing_field("values"))?;
-            let value = values.as_array()
-                // TODO: support multiple values
-                // (which are probably used for multiple kinds of elemental damage)
-                .and_then(|v| v.get(0)).and_then(|v| v.as_array())
+            // Multiple values are used e.g. for multiple kinds of elemental damage,
+            // or flask charges (per use & maximum).
+            let values: Vec<_> = values.as_array().into_iter().flat_map(|vs| vs.iter())
+                .filter_map(|v| v.as_array())
                 // TODO interpret values[i][1], which has to do with damage types
                 // (physical, fire, etc.) or whether the value has been modified
                 // by an affix on them item
-                .and_then(|v| v.get(0)).and_then(|v| v.as_str().map(|s| s.to_owned()));
+                .filter_map(|v| v.get(0)).filter_map(|v| v.as_str().map(|s| s.to_owned()))
+                .collect();
 
             // If there is no value, we split the key by commas
             // and potentially insert multiple properties.
             // This handles the common gem "tags", like "Support", "Projectile", etc.,
             // which for some reason are lumped together in a single comma-separated property.
-            match value {
-                Some(val) => { result.put_with_value(name, val); },
-                None => {
-                    for key in name.split(',') {
-                        result.put(key.trim().to_owned());
-                    }
+            if values.is_empty() {
+                for key in name.split(',') {
+                    result.put(key.trim().to_owned());
                 }
+            } else {
+                result.put_with_values(name, values);
             }
         }
 
@@ -7,7 +7,7 @@ use itertools::Itertools;
 use serde::de::{self, Deserialize, Visitor};
 use serde_json::Value as Json;
 
-use super::super::{ItemSockets, SocketGroup};
+use super::super::{ItemSockets, SocketGroup, SocketPosition};
 use super::util::deserialize;
 
 
@@ -40,11 +40,13 @@ impl<'de> Visitor<'de> for ItemSocketsVisitor {
         let mut abyssal_count = 0;
         let mut regular_groups = HashMap::new();
         let mut indices = HashSet::new();
+        let mut positions = Vec::new();
 
         while let Some(socket) = seq.next_element::<HashMap<String, Json>>()? {
             let color = socket.get("sColour").and_then(|c| c.as_str())
                 .ok_or_else(|| de::Error::missing_field("sColour"))?;
             if color == "A" {
+                positions.push(SocketPosition::Abyssal(abyssal_count));
                 abyssal_count += 1;
                 continue;
             }
@@ -52,8 +54,9 @@ impl<'de> Visitor<'de> for ItemSocketsVisitor {
                 .ok_or_else(|| de::Error::missing_field("group"))?;
             let regular_color = deserialize(color)?;
 
-            regular_groups.entry(group).or_insert_with(Vec::new)
-                .push(regular_color);
+            let group_colors = regular_groups.entry(group).or_insert_with(Vec::new);
+            positions.push(SocketPosition::Regular{group: group as u8, index: group_colors.len()});
+            group_colors.push(regular_color);
             indices.insert(group);
         }
 
@@ -68,9 +71,10 @@ impl<'de> Visitor<'de> for ItemSocketsVisitor {
 
         let regular_groups = regular_groups.into_iter()
             .sorted_by_key(|&(i, _)| i).into_iter()
-            .map(|(i, g)| SocketGroup{id: i as u8, colors: g})
+            .map(|(i, g)| SocketGroup::new(i as u8, g))
             .collect();
+        let abyssal_items = (0..abyssal_count).map(|_| None).collect();
 
-        Ok(ItemSockets{abyssal_count, regular_groups})
+        Ok(ItemSockets{abyssal_items, regular_groups, positions})
     }
 }
@@ -35,7 +35,7 @@ impl<'de> Visitor<'de> for StashVisitor {
         // (See the botoom of the function for the rationale behind nested Options).
         let mut id = None;
         let mut league = None;
-        let mut label: Option<Option<_>> = None;
+        let mut name: Option<Option<String>> = None;
         let mut type_ = None;
         let mut account: Option<Option<_>> = None;
         let mut last_character = None;
@@ -49,8 +49,8 @@ impl<'de> Visitor<'de> for StashVisitor {
                     id = Some(map.next_value()?);
                 }
                 "stash" => {
-                    check_duplicate!("stash" => label);
-                    label = Some(map.next_value()?);
+                    check_duplicate!("stash" => name);
+                    name = Some(map.next_value()?);
                 }
                 "stashType" => {
                     check_duplicate!("stashType" => type_);
@@ -100,19 +100,21 @@ impl<'de> Visitor<'de> for StashVisitor {
         }
 
         let id = id.ok_or_else(|| de::Error::missing_field("id"))?;
-        let label = label.ok_or_else(|| de::Error::missing_field("stash"))?;
+        let name = name.ok_or_else(|| de::Error::missing_field("stash"))?;
         let league = league.unwrap_or_default();
         let type_ = type_.ok_or_else(|| de::Error::missing_field("stashType"))?;
         let account = account.ok_or_else(|| de::Error::missing_field("accountName"))?;
         let last_character = last_character.unwrap_or_default();
         let items = items.unwrap_or_default();
 
+        // Historical records seem to include some broken stashes
+        // where "stash" and "accountName" keys are null.
+        // We'll just convert them to empty strings.
+        let name = name.unwrap_or_default();
+        let label = deserialize(name.as_str())?;
+
         Ok(Stash{
-            id, league, type_, last_character, items,
-            // Historical records seem to include some broken stashes
-            // where "stash" and "accountName" keys are null.
-            // We'll just convert them to empty strings.
-            label: label.unwrap_or_default(),
+            id, league, name, label, type_, last_character, items,
             account: account.unwrap_or_default(),
         })
     }
@@ -7,7 +7,7 @@ use serde::de::{self, Deserialize, Deserializer, IntoDeserializer};
 
 /// Macro for checking duplicate fields when deserializing from a map or struct.
 macro_rules! check_duplicate {
-    // E.g.: check_duplicate!("frameType" => rarity)
+    // E.g.: check_duplicate!("frameType" => frame_type)
     ($key:expr => $var:ident) => ({
         if $var.is_some() {
             return Err(::serde::de::Error::duplicate_field($key));
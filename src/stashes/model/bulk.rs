@@ -0,0 +1,279 @@
+//! Bulk trading of stackable items, like currency and divination cards.
+
+use std::cmp::Ordering;
+
+use super::currency::Currency;
+use super::item::{Item, ItemCategory};
+use super::price::Price;
+use super::stash::{Stash, StashType, StashedItem};
+
+
+/// Stackable item that is traded in bulk.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum BulkItem {
+    /// Currency item.
+    Currency(Currency),
+    /// Divination card of given name.
+    DivinationCard(String),
+}
+
+impl BulkItem {
+    /// Determine what stackable item is given `Item`, if it's traded in bulk.
+    pub fn of(item: &Item) -> Option<Self> {
+        if let Some(currency) = item.as_currency() {
+            return Some(BulkItem::Currency(currency));
+        }
+        match item.category.as_ref() {
+            Some(&ItemCategory::DivinationCard) => Some(BulkItem::DivinationCard(item.base.clone())),
+            _ => None,
+        }
+    }
+}
+
+
+/// Offer of a seller to trade some quantity of a stackable item.
+#[derive(Clone, Debug)]
+pub struct BulkOffer<'s> {
+    /// Name of the seller's account.
+    account: &'s str,
+    /// Name of the seller's last character, if known.
+    last_character: Option<&'s str>,
+    /// The item being offered.
+    item: BulkItem,
+    /// Total quantity of the item on offer.
+    quantity: u32,
+    /// Price of a single unit of the item.
+    unit_price: Price,
+}
+
+impl<'s> BulkOffer<'s> {
+    /// Name of the seller's account.
+    #[inline]
+    pub fn account(&self) -> &'s str {
+        self.account
+    }
+
+    /// Name of the last character the seller has logged in with, if known.
+    #[inline]
+    pub fn last_character(&self) -> Option<&'s str> {
+        self.last_character
+    }
+
+    /// The stackable item being offered.
+    #[inline]
+    pub fn item(&self) -> &BulkItem {
+        &self.item
+    }
+
+    /// Total quantity of the item on offer.
+    #[inline]
+    pub fn quantity(&self) -> u32 {
+        self.quantity
+    }
+
+    /// Price of a single unit of the item.
+    #[inline]
+    pub fn unit_price(&self) -> &Price {
+        &self.unit_price
+    }
+
+    /// Price for buying given quantity of the item.
+    pub fn price_for(&self, quantity: u32) -> Price {
+        let amount = self.unit_price.amount() * quantity as f64;
+        Price::new(amount, self.unit_price.currency().clone())
+    }
+}
+
+
+impl Stash {
+    /// Offers of stackable items in this stash tab,
+    /// if it is a currency or divination card tab.
+    ///
+    /// Every stack is priced with its own label or, failing that, the label of the tab.
+    /// Like on the official trade site, the price is taken to be that of a single unit,
+    /// regardless of the stack size.
+    ///
+    /// Stacks of the same item for the same unit price are combined into a single offer.
+    pub fn bulk_offers(&self) -> Vec<BulkOffer> {
+        match self.type_ {
+            StashType::Currency | StashType::Divination => {}
+            _ => return vec![],
+        }
+        let mut offers = vec![];
+        for stashed in &self.items {
+            let item = match BulkItem::of(stashed) {
+                Some(item) => item,
+                None => continue,
+            };
+            let unit_price = match stack_price(self, stashed) {
+                Some(price) => price.clone(),
+                None => continue,
+            };
+            add_offer(&mut offers, BulkOffer{
+                account: &self.account,
+                last_character: self.last_character.as_ref().map(|c| c.as_str()),
+                item,
+                quantity: stashed.stack_size(),
+                unit_price,
+            });
+        }
+        offers
+    }
+}
+
+/// Collect the offers of stackable items from given stash tabs.
+///
+/// Offers of the same seller for the same item and unit price
+/// are combined even if they come from different stash tabs.
+pub fn bulk_offers<'s, I>(stashes: I) -> Vec<BulkOffer<'s>>
+    where I: IntoIterator<Item=&'s Stash>
+{
+    let mut offers = vec![];
+    for offer in stashes.into_iter().flat_map(|s| s.bulk_offers()) {
+        add_offer(&mut offers, offer);
+    }
+    offers
+}
+
+/// Find the offers that can fill an order for given quantity of a stackable item,
+/// paid for in given currency.
+///
+/// The offers are sorted by their unit price, so the cheapest sellers come first.
+/// Offers priced in other currencies, or with less than the requested quantity
+/// on offer, are omitted.
+pub fn match_bulk<'s, I>(stashes: I, wanted: &BulkItem, quantity: u32,
+                         currency: Currency) -> Vec<BulkOffer<'s>>
+    where I: IntoIterator<Item=&'s Stash>
+{
+    let mut offers: Vec<_> = bulk_offers(stashes).into_iter()
+        .filter(|o| o.item == *wanted && o.quantity >= quantity)
+        .filter(|o| o.unit_price.currency().as_ref() == Some(&currency))
+        .collect();
+    offers.sort_by(|a, b| {
+        a.unit_price.partial_cmp(&b.unit_price).unwrap_or(Ordering::Equal)
+    });
+    offers
+}
+
+
+/// Price of a stack of items in the stash tab.
+fn stack_price<'s>(stash: &'s Stash, stashed: &'s StashedItem) -> Option<&'s Price> {
+    stashed.label().and_then(|l| l.price()).or_else(|| stash.label.price())
+}
+
+/// Add an offer to the list, combining it with a matching one if it's already there.
+fn add_offer<'s>(offers: &mut Vec<BulkOffer<'s>>, offer: BulkOffer<'s>) {
+    let existing = offers.iter_mut().find(|o| {
+        o.account == offer.account && o.item == offer.item && o.unit_price == offer.unit_price
+    });
+    match existing {
+        Some(o) => o.quantity += offer.quantity,
+        None => offers.push(offer),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use serde_json::from_value;
+    use super::super::currency::Currency;
+    use super::super::stash::Stash;
+    use super::{BulkItem, bulk_offers, match_bulk};
+
+    #[test]
+    fn combined_offers() {
+        let stash: Stash = from_value(json!({
+            "id": "foo",
+            "accountName": "foo", "lastCharacterName": null,
+            "stash": "~price 1 chaos", "stashType": "CurrencyStash",
+            "items": [{
+                "id": "foo-0",
+                "name": "", "typeLine": "Orb of Alteration", "ilvl": 0,
+                "category": "currency", "frameType": 5,
+                "stackSize": 100, "maxStackSize": 5000,
+                "league": "Standard", "x": 0, "y": 0, "w": 1, "h": 1,
+            }, {
+                "id": "foo-1",
+                "name": "", "typeLine": "Orb of Alteration", "ilvl": 0,
+                "category": "currency", "frameType": 5,
+                "stackSize": 50, "maxStackSize": 5000,
+                "league": "Standard", "x": 1, "y": 0, "w": 1, "h": 1,
+            }, {
+                "id": "foo-2",
+                "name": "", "typeLine": "Orb of Fusing", "ilvl": 0,
+                "category": "currency", "frameType": 5,
+                "stackSize": 20, "maxStackSize": 5000,
+                "note": "~b/o 3 exa", "league": "Standard",
+                "x": 2, "y": 0, "w": 1, "h": 1,
+            }],
+        })).unwrap();
+        let offers = bulk_offers(vec![&stash]);
+        assert_eq!(2, offers.len());
+        assert_eq!(&BulkItem::Currency(Currency::OrbOfAlteration), offers[0].item());
+        assert_eq!(150, offers[0].quantity());
+        assert_eq!(1.0, offers[0].unit_price().amount());
+        assert_eq!(40.0, offers[0].price_for(40).amount());
+    }
+
+    #[test]
+    fn matching() {
+        let stashes: Vec<Stash> = vec![
+            json!({
+                "id": "expensive",
+                "accountName": "expensive", "lastCharacterName": null,
+                "stash": "", "stashType": "CurrencyStash",
+                "items": [{
+                    "id": "expensive-0",
+                    "name": "", "typeLine": "Orb of Fusing", "ilvl": 0,
+                    "category": "currency", "frameType": 5,
+                    "stackSize": 100, "maxStackSize": 5000,
+                    "note": "~price 1 chaos", "league": "Standard",
+                    "x": 0, "y": 0, "w": 1, "h": 1,
+                }],
+            }),
+            json!({
+                "id": "cheap",
+                "accountName": "cheap", "lastCharacterName": null,
+                "stash": "", "stashType": "CurrencyStash",
+                "items": [{
+                    "id": "cheap-0",
+                    "name": "", "typeLine": "Orb of Fusing", "ilvl": 0,
+                    "category": "currency", "frameType": 5,
+                    "stackSize": 100, "maxStackSize": 5000,
+                    "note": "~price 1/2 chaos", "league": "Standard",
+                    "x": 0, "y": 0, "w": 1, "h": 1,
+                }],
+            }),
+            json!({
+                "id": "small",
+                "accountName": "small", "lastCharacterName": null,
+                "stash": "", "stashType": "CurrencyStash",
+                "items": [{
+                    "id": "small-0",
+                    "name": "", "typeLine": "Orb of Fusing", "ilvl": 0,
+                    "category": "currency", "frameType": 5,
+                    "stackSize": 10, "maxStackSize": 5000,
+                    "note": "~price 1/4 chaos", "league": "Standard",
+                    "x": 0, "y": 0, "w": 1, "h": 1,
+                }],
+            }),
+            json!({
+                "id": "other",
+                "accountName": "other", "lastCharacterName": null,
+                "stash": "", "stashType": "CurrencyStash",
+                "items": [{
+                    "id": "other-0",
+                    "name": "", "typeLine": "Orb of Fusing", "ilvl": 0,
+                    "category": "currency", "frameType": 5,
+                    "stackSize": 100, "maxStackSize": 5000,
+                    "note": "~price 1/100 exa", "league": "Standard",
+                    "x": 0, "y": 0, "w": 1, "h": 1,
+                }],
+            }),
+        ].into_iter().map(|s| from_value(s).unwrap()).collect();
+        let offers = match_bulk(&stashes, &BulkItem::Currency(Currency::OrbOfFusing),
+                                50, Currency::ChaosOrb);
+        let accounts: Vec<_> = offers.iter().map(|o| o.account()).collect();
+        assert_eq!(vec!["cheap", "expensive"], accounts);
+    }
+}
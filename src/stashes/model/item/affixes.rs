@@ -0,0 +1,520 @@
+//! Module inferring the affixes (prefixes & suffixes) of items from their mods.
+
+use std::ptr;
+
+use super::{Item, ItemCategory, Mod, Rarity};
+use super::mods::{Affix, AffixType, ModId, ModType};
+use super::mods::tier::{affixes_for, affixes_named};
+
+
+/// Maximum number of affix assignments that `Item::affixes` will produce.
+const MAX_ASSIGNMENTS: usize = 256;
+
+
+/// A possible assignment of item's explicit mods to the affixes that produced them.
+#[derive(Clone, Debug)]
+pub struct AffixAssignment<'i> {
+    /// Affixes along with the mods they've produced.
+    ///
+    /// Regular affixes produce a single mod, while hybrid ones produce more.
+    affixes: Vec<(&'static Affix, Vec<&'i Mod>)>,
+    /// Mods which couldn't be attributed to any known affix.
+    unknown: Vec<&'i Mod>,
+}
+
+impl<'i> AffixAssignment<'i> {
+    /// Affixes of the item, along with the explicit mods they produce.
+    ///
+    /// Note that a single mod may be produced by more than one affix
+    /// (e.g. a regular and a hybrid one) when their values add up.
+    pub fn affixes<'a>(&'a self) -> Box<Iterator<Item=(&'static Affix, &'a [&'i Mod])> + 'a> {
+        Box::new(self.affixes.iter().map(|&(a, ref mods)| (a, mods.as_slice())))
+    }
+
+    /// Prefixes of the item, along with the explicit mods they produce.
+    pub fn prefixes<'a>(&'a self) -> Box<Iterator<Item=(&'static Affix, &'a [&'i Mod])> + 'a> {
+        Box::new(self.affixes().filter(|&(a, _)| a.affix_type() == AffixType::Prefix))
+    }
+
+    /// Suffixes of the item, along with the explicit mods they produce.
+    pub fn suffixes<'a>(&'a self) -> Box<Iterator<Item=(&'static Affix, &'a [&'i Mod])> + 'a> {
+        Box::new(self.affixes().filter(|&(a, _)| a.affix_type() == AffixType::Suffix))
+    }
+
+    /// Explicit mods which couldn't be attributed to any known affix.
+    #[inline]
+    pub fn unknown(&self) -> &[&'i Mod] {
+        self.unknown.as_slice()
+    }
+
+    /// Number of prefixes in this assignment.
+    #[inline]
+    pub fn prefix_count(&self) -> usize {
+        self.prefixes().count()
+    }
+
+    /// Number of suffixes in this assignment.
+    #[inline]
+    pub fn suffix_count(&self) -> usize {
+        self.suffixes().count()
+    }
+}
+
+
+impl Item {
+    /// Infer which affixes have produced the explicit mods of the item.
+    ///
+    /// Because of hybrid affixes (which produce more than one mod)
+    /// and mods whose values can be a sum of several affixes,
+    /// this is not always unambiguous.
+    /// Hence the result contains all possible assignments of mods to affixes
+    /// that are consistent with the known affix tiers.
+    ///
+    /// If the item doesn't have explicit mods, or they cannot be reconciled
+    /// with the affix data, the result is empty.
+    pub fn affixes(&self) -> Vec<AffixAssignment> {
+        let (explicit, category): (Vec<_>, _) = match (self.as_gear(), self.category.as_ref()) {
+            (Some(gear), Some(category)) => (gear.explicit_mods().collect(), category),
+            _ => return vec![],
+        };
+        if explicit.is_empty() {
+            return vec![];
+        }
+
+        // Mods that cannot come from any known affix are set aside as unknown.
+        let (mut mods, mut candidates, mut unknown) = (vec![], vec![], vec![]);
+        for mod_ in explicit {
+            let affixes = candidate_affixes(mod_, category, self.level);
+            if affixes.is_empty() {
+                unknown.push(mod_);
+            } else {
+                mods.push(mod_);
+                candidates.push(affixes);
+            }
+        }
+
+        let mut solver = Solver::new(mods.as_slice(), candidates);
+        solver.solve(0);
+        solver.results.into_iter().map(|chosen| AffixAssignment{
+            affixes: chosen.into_iter()
+                .map(|(affix, indices)| (affix, indices.into_iter().map(|i| mods[i]).collect()))
+                .collect(),
+            unknown: unknown.clone(),
+        }).collect()
+    }
+
+    /// Groups of explicit mods that have been produced by a single hybrid affix
+    /// (e.g. "+# to Armour" with "+# to maximum Life"), along with that affix.
+    ///
+    /// Only the groups which are the same in every possible result of `affixes`
+    /// are returned, so the result is empty if the affixes are too ambiguous to tell.
+    pub fn hybrid_mods(&self) -> Vec<(&'static Affix, Vec<&Mod>)> {
+        let assignments = self.affixes();
+        let (first, rest) = match assignments.split_first() {
+            Some(split) => split,
+            None => return vec![],
+        };
+        let same_group = |a: &AffixAssignment, affix: &Affix, mods: &[&Mod]| {
+            a.affixes().any(|(other, other_mods)| ptr::eq(affix, other)
+                && other_mods.len() == mods.len()
+                && other_mods.iter().zip(mods).all(|(&m1, &m2)| ptr::eq(m1, m2)))
+        };
+        first.affixes()
+            .filter(|&(affix, mods)| affix.is_hybrid() && mods.len() > 1)
+            .filter(|&(affix, mods)| rest.iter().all(|a| same_group(a, affix, mods)))
+            .map(|(affix, mods)| (affix, mods.to_vec()))
+            .collect()
+    }
+
+    /// Affixes of a magic item, as identified by their names in the item's name
+    /// (e.g. "Virile" and "of the Titan" in "Virile Vaal Regalia of the Titan").
+    ///
+    /// Each affix is returned along with the explicit mods it has produced.
+    /// Affixes whose names aren't known, or which don't match the item's mods,
+    /// are omitted.
+    pub fn named_affixes(&self) -> Vec<(&'static Affix, Vec<&Mod>)> {
+        let (explicit, category): (Vec<_>, _) = match (self.as_gear(), self.category.as_ref()) {
+            (Some(gear), Some(category)) => (gear.explicit_mods().collect(), category),
+            _ => return vec![],
+        };
+        let names = vec![
+            (AffixType::Prefix, self.name_prefix()),
+            (AffixType::Suffix, self.name_suffix()),
+        ];
+
+        let mut result = vec![];
+        for (affix_type, name) in names {
+            let name = match name { Some(n) => n, None => continue };
+            // Out of the affixes with this name, prefer the one which fits the mod values.
+            let matches: Vec<_> = affixes_named(affix_type, name).into_iter()
+                .filter(|a| a.can_roll_on(category, self.level))
+                .filter_map(|affix| {
+                    let mods: Vec<_> = affix.stats().iter()
+                        .filter_map(|stat| explicit.iter().find(|m| mod_id(m) == Some(stat.mod_id()))
+                            .map(|&m| (stat, m)))
+                        .collect();
+                    if mods.len() < affix.stats().len() {
+                        return None;
+                    }
+                    let fits = mods.iter().all(|&(stat, m)| m.values().map(|v| stat.contains(v)).unwrap_or(false));
+                    Some((fits, affix, mods.into_iter().map(|(_, m)| m).collect()))
+                })
+                .collect();
+            let best = matches.iter().position(|&(fits, _, _)| fits).unwrap_or(0);
+            if let Some((_, affix, mods)) = matches.into_iter().nth(best) {
+                result.push((affix, mods));
+            }
+        }
+        result
+    }
+
+    /// Bounds on the number of prefixes that are open on the item,
+    /// i.e. how many more of them could be added to the item (e.g. by crafting).
+    ///
+    /// The result is a `(minimum, maximum)` pair which accounts for both
+    /// the ambiguity of `affixes` and for the crafted mods.
+    /// Returns `None` for items which cannot have prefixes
+    /// or whose mods aren't known (e.g. unidentified ones).
+    #[inline]
+    pub fn open_prefixes(&self) -> Option<(usize, usize)> {
+        self.open_affixes(AffixType::Prefix)
+    }
+
+    /// Bounds on the number of suffixes that are open on the item,
+    /// i.e. how many more of them could be added to the item (e.g. by crafting).
+    ///
+    /// The result is a `(minimum, maximum)` pair which accounts for both
+    /// the ambiguity of `affixes` and for the crafted mods.
+    /// Returns `None` for items which cannot have suffixes
+    /// or whose mods aren't known (e.g. unidentified ones).
+    #[inline]
+    pub fn open_suffixes(&self) -> Option<(usize, usize)> {
+        self.open_affixes(AffixType::Suffix)
+    }
+
+    fn open_affixes(&self, affix_type: AffixType) -> Option<(usize, usize)> {
+        let max = self.max_affixes()?;
+        let gear = self.as_gear()?;
+        let (explicit, veiled, crafted) = (&gear.explicit, &gear.veiled, &gear.crafted);
+
+        // Count the affixes of given type ("own") and the other type,
+        // along with those which could be of either.
+        let mut counts: Vec<(usize, usize, usize)> = self.affixes().iter().map(|a| {
+            let own = a.affixes().filter(|&(a, _)| a.affix_type() == affix_type).count();
+            (own, a.affixes.len() - own, a.unknown.len())
+        }).collect();
+        if counts.is_empty() {
+            counts.push((0, 0, gear.fractured.len() + explicit.len()));
+        }
+        for mod_ in veiled {
+            for &mut (ref mut own, ref mut other, ref mut unknown) in &mut counts {
+                match veiled_affix_type(mod_) {
+                    Some(t) if t == affix_type => *own += 1,
+                    Some(_) => *other += 1,
+                    None => *unknown += 1,
+                }
+            }
+        }
+        for mod_ in crafted {
+            for &mut (ref mut own, ref mut other, ref mut unknown) in &mut counts {
+                match crafted_affix_type(mod_) {
+                    Some(t) if t == affix_type => *own += 1,
+                    Some(_) => *other += 1,
+                    None => *unknown += 1,
+                }
+            }
+        }
+
+        let bounds: Vec<_> = counts.into_iter()
+            .filter(|&(own, other, _)| own <= max && other <= max)
+            .map(|(own, other, unknown)| {
+                let min_used = own + unknown.saturating_sub(max - other);
+                let max_used = (own + unknown).min(max);
+                (max - max_used, max - min_used.min(max))
+            })
+            .collect();
+        if bounds.is_empty() {
+            return Some((0, 0));
+        }
+        Some((bounds.iter().map(|&(lo, _)| lo).min().unwrap(),
+              bounds.iter().map(|&(_, hi)| hi).max().unwrap()))
+    }
+
+    /// Maximum number of prefixes (and separately, suffixes) that the item can have.
+    fn max_affixes(&self) -> Option<usize> {
+        let is_jewel = match self.category.as_ref() {
+            Some(&ItemCategory::Jewel(..)) => true,
+            Some(c) if c.is_modifiable() => false,
+            _ => return None,
+        };
+        match self.rarity {
+            Rarity::Magic => Some(1),
+            Rarity::Rare => Some(if is_jewel { 2 } else { 3 }),
+            _ => None,
+        }
+    }
+}
+
+
+/// Returns the affixes which could have produced given explicit mod
+/// on an item of given category and item level.
+fn candidate_affixes(mod_: &Mod, category: &ItemCategory, item_level: u64) -> Vec<&'static Affix> {
+    let id = match mod_.info() {
+        Some(mi) => mi.id(),
+        None => return vec![],
+    };
+    affixes_for(id).iter()
+        .filter(|a| a.can_roll_on(category, item_level))
+        .map(|a| &**a)
+        .collect()
+}
+
+/// Determine whether a crafted mod is a prefix or a suffix.
+///
+/// This assumes crafted mods share their affix type with the explicit mods of the same stat.
+fn crafted_affix_type(mod_: &Mod) -> Option<AffixType> {
+    let number = mod_.info()?.id().mod_number()?;
+    let affixes = affixes_for(&ModId::new(ModType::Explicit, number));
+    let affix_type = affixes.first()?.affix_type();
+    if affixes.iter().all(|a| a.affix_type() == affix_type) {
+        Some(affix_type)
+    } else {
+        None
+    }
+}
+
+/// Determine whether a veiled mod is a prefix or a suffix.
+fn veiled_affix_type(mod_: &Mod) -> Option<AffixType> {
+    match mod_.as_str() {
+        "Veiled Prefix" => Some(AffixType::Prefix),
+        "Veiled Suffix" => Some(AffixType::Suffix),
+        _ => None,
+    }
+}
+
+
+/// Backtracking search for the assignments of mods to affixes.
+struct Solver<'m, 'i: 'm> {
+    /// Mods that need to be produced by the affixes.
+    mods: &'m [&'i Mod],
+    /// Affixes which could (partially) produce each mod.
+    candidates: Vec<Vec<&'static Affix>>,
+    /// Affixes which have been chosen so far to produce each mod.
+    contributors: Vec<Vec<&'static Affix>>,
+    /// Affixes chosen so far, with indices of the mods they produce.
+    chosen: Vec<(&'static Affix, Vec<usize>)>,
+    /// Complete assignments found.
+    results: Vec<Vec<(&'static Affix, Vec<usize>)>>,
+}
+
+impl<'m, 'i> Solver<'m, 'i> {
+    fn new(mods: &'m [&'i Mod], candidates: Vec<Vec<&'static Affix>>) -> Self {
+        Solver {
+            mods, candidates,
+            contributors: vec![vec![]; mods.len()],
+            chosen: vec![],
+            results: vec![],
+        }
+    }
+
+    /// Find affixes for the i-th mod and all the ones after it.
+    fn solve(&mut self, i: usize) {
+        if self.results.len() >= MAX_ASSIGNMENTS {
+            return;
+        }
+        if i == self.mods.len() {
+            self.results.push(self.chosen.clone());
+            return;
+        }
+
+        // The mod may have been produced by a single affix, or by a hybrid one
+        // and a regular one whose values have been added together.
+        // Hybrid affixes may have already been chosen for one of the previous mods.
+        let mut options: Vec<Vec<&'static Affix>> = vec![];
+        let candidates = self.candidates[i].clone();
+        if self.contributors[i].is_empty() {
+            options.extend(candidates.iter().map(|&a| vec![a]));
+            for &hybrid in candidates.iter().filter(|a| a.is_hybrid()) {
+                for &regular in candidates.iter().filter(|a| !a.is_hybrid()) {
+                    options.push(vec![hybrid, regular]);
+                }
+            }
+        } else {
+            options.push(vec![]);
+            if self.contributors[i].iter().all(|a| a.is_hybrid()) {
+                options.extend(candidates.iter().filter(|a| !a.is_hybrid()).map(|&a| vec![a]));
+            }
+        }
+
+        for option in options {
+            let mut chosen = 0;
+            for &affix in &option {
+                if !self.choose(affix, i) {
+                    break;
+                }
+                chosen += 1;
+            }
+            if chosen == option.len() && self.fits(i) {
+                self.solve(i + 1);
+            }
+            for _ in 0..chosen {
+                self.unchoose();
+            }
+        }
+    }
+
+    /// Choose given affix to produce the i-th mod (and possibly some subsequent ones).
+    /// Returns whether that was possible.
+    fn choose(&mut self, affix: &'static Affix, i: usize) -> bool {
+        // Items can't have two affixes with the same stats, even of different tiers.
+        if self.chosen.iter().any(|&(a, _)| a.has_same_stats(affix)) {
+            return false;
+        }
+
+        // Hybrid affixes must have all their mods present on the item,
+        // and they are only considered for the first of them.
+        let mut indices = Vec::with_capacity(affix.stats().len());
+        for stat in affix.stats() {
+            match self.mods.iter().position(|m| mod_id(m) == Some(stat.mod_id())) {
+                Some(j) if j >= i => indices.push(j),
+                _ => return false,
+            }
+        }
+        for &j in &indices {
+            self.contributors[j].push(affix);
+        }
+        self.chosen.push((affix, indices));
+        true
+    }
+
+    fn unchoose(&mut self) {
+        let (_, indices) = self.chosen.pop().unwrap();
+        for j in indices {
+            self.contributors[j].pop();
+        }
+    }
+
+    /// Whether the values of i-th mod match the sum of its contributing affixes' ranges.
+    fn fits(&self, i: usize) -> bool {
+        let contributors = &self.contributors[i];
+        let (id, values) = match (mod_id(self.mods[i]), self.mods[i].values()) {
+            (Some(id), Some(values)) => (id, values),
+            _ => return false,
+        };
+        !contributors.is_empty() && values.iter().enumerate().all(|(k, v)| {
+            let (min, max) = contributors.iter()
+                .filter_map(|a| a.stats().iter().find(|s| s.mod_id() == id))
+                .filter_map(|s| s.ranges().get(k))
+                .fold((0.0, 0.0), |(min, max), &(lo, hi)| (min + lo, max + hi));
+            min <= v && v <= max
+        })
+    }
+}
+
+fn mod_id(mod_: &Mod) -> Option<&ModId> {
+    mod_.info().map(|mi| mi.id())
+}
+
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use serde_json::from_value;
+    use ::stashes::Item;
+
+    #[test]
+    fn affixes() {
+        let item: Item = from_value(json!({
+            "id": "0123456789abcdef",
+            "name": "Foo Bar", "typeLine": "Vaal Regalia",
+            "ilvl": 84, "frameType": 2, "identified": true,
+            "category": {"armour": ["chest"]},
+            "explicitMods": [
+                "+105 to maximum Life",
+                "25% increased Armour",
+                "+40% to Fire Resistance",
+                "10% increased Stun and Block Recovery"
+            ],
+            "icon": "", "league": "Standard", "verified": false, "w": 2, "h": 3
+        })).unwrap();
+        let assignments = item.affixes();
+        assert_eq!(1, assignments.len());
+        let affixes = &assignments[0];
+        assert_eq!(2, affixes.prefix_count());
+        assert_eq!(1, affixes.suffix_count());
+        assert!(affixes.unknown().is_empty());
+        assert!(affixes.prefixes().any(|(a, mods)| a.is_hybrid() && mods.len() == 2));
+
+        let hybrid = item.hybrid_mods();
+        assert_eq!(1, hybrid.len());
+        assert_eq!(2, hybrid[0].1.len());
+
+        assert_eq!(Some((1, 1)), item.open_prefixes());
+        assert_eq!(Some((2, 2)), item.open_suffixes());
+    }
+
+    #[test]
+    fn named_affixes() {
+        let item: Item = from_value(json!({
+            "id": "0123456789abcdef",
+            "name": "", "typeLine": "Virile Vaal Regalia of the Titan",
+            "ilvl": 84, "frameType": 1, "identified": true,
+            "category": {"armour": ["chest"]},
+            "explicitMods": ["+45 to Strength", "+75 to maximum Life"],
+            "icon": "", "league": "Standard", "verified": false, "w": 2, "h": 3
+        })).unwrap();
+        let affixes = item.named_affixes();
+        assert_eq!(2, affixes.len());
+        assert_eq!("Virile", affixes[0].0.name());
+        assert_eq!("+75 to maximum Life", affixes[0].1[0].as_str());
+        assert_eq!("of the Titan", affixes[1].0.name());
+        assert_eq!(Some((0, 0)), item.open_prefixes());
+    }
+
+    #[test]
+    fn open_affixes__crafted() {
+        let item: Item = from_value(json!({
+            "id": "0123456789abcdef",
+            "name": "Foo Bar", "typeLine": "Vaal Regalia",
+            "ilvl": 84, "frameType": 2, "identified": true,
+            "category": {"armour": ["chest"]},
+            "explicitMods": ["+105 to maximum Life"],
+            "craftedMods": ["+20 to Strength"],
+            "icon": "", "league": "Standard", "verified": false, "w": 2, "h": 3
+        })).unwrap();
+        assert_eq!(Some((2, 2)), item.open_prefixes());
+        assert_eq!(Some((2, 2)), item.open_suffixes());
+    }
+
+    #[test]
+    fn open_affixes__unknown_mods() {
+        let item: Item = from_value(json!({
+            "id": "0123456789abcdef",
+            "name": "Foo Bar", "typeLine": "Vaal Regalia",
+            "ilvl": 84, "frameType": 2, "identified": true,
+            "category": {"armour": ["chest"]},
+            "explicitMods": ["+105 to maximum Life", "Totally Unknown Mod"],
+            "icon": "", "league": "Standard", "verified": false, "w": 2, "h": 3
+        })).unwrap();
+        assert_eq!(1, item.affixes()[0].unknown().len());
+        assert_eq!(Some((1, 2)), item.open_prefixes());
+        assert_eq!(Some((2, 3)), item.open_suffixes());
+    }
+
+    #[test]
+    fn open_affixes__fractured_and_veiled() {
+        let item: Item = from_value(json!({
+            "id": "0123456789abcdef",
+            "name": "Foo Bar", "typeLine": "Vaal Regalia",
+            "ilvl": 84, "frameType": 2, "identified": true, "fractured": true,
+            "category": {"armour": ["chest"]},
+            "fracturedMods": ["+105 to maximum Life"],
+            "explicitMods": ["+40% to Fire Resistance"],
+            "veiledMods": ["Veiled Prefix"],
+            "icon": "", "league": "Standard", "verified": false, "w": 2, "h": 3
+        })).unwrap();
+        assert_eq!(2, item.affixes()[0].affixes().count());
+        assert_eq!(Some((1, 1)), item.open_prefixes());
+        assert_eq!(Some((2, 2)), item.open_suffixes());
+    }
+}
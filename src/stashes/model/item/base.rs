@@ -0,0 +1,389 @@
+//! Item base types.
+//!
+//! The data about known bases is compiled in by the build script
+//! from `data/bases.json` and `data/maps.json`.
+
+use std::collections::HashMap;
+
+use super::{
+    AccessoryType, ArmourType, Defences, Item, ItemCategory, JewelType, Requirement,
+    WeaponType,
+};
+#[cfg(feature = "mods_db")]
+use super::mods::AffixType;
+use super::mods::{ModId, ModType, ModValue};
+#[cfg(feature = "mods_db")]
+use super::mods::ModValues;
+#[cfg(feature = "mods_db")]
+use super::mods::tier::affix_names;
+
+
+lazy_static! {
+    /// Information about all known item base types, by their names.
+    static ref BASES: HashMap<&'static str, BaseInfo> = {
+        let bases: Vec<BaseInfo> = include!(concat!(
+            env!("OUT_DIR"), "/", "model/item/base/infos.inc.rs"));
+        bases.into_iter().map(|b| (b.name, b)).collect()
+    };
+}
+
+
+/// Information about an item base type, like "Vaal Regalia" or "Academy Map".
+#[derive(Clone, Debug)]
+pub struct BaseInfo {
+    /// Name of the base type.
+    name: &'static str,
+    /// Item class of the base, e.g. "Body Armour" or "One Hand Sword".
+    class: &'static str,
+    /// Category of items of this base.
+    category: ItemCategory,
+    /// Minimum level of the area that items of this base can drop in, if known.
+    level: Option<u64>,
+    /// Dimensions of the item (`(width, height)`) in inventory tiles, if known.
+    size: Option<(u64, u64)>,
+    /// Requirements for using items of this base (other than their mods').
+    requirements: HashMap<Requirement, u32>,
+    /// Implicit mods that items of this base have by default, if known.
+    implicits: Option<Vec<BaseImplicit>>,
+    /// Base defences of armour items (without quality or mods).
+    defences: Option<Defences>,
+    /// Base stats of weapons (without quality or mods).
+    weapon: Option<BaseWeaponStats>,
+}
+
+/// Implicit mod that items of a particular base have by default.
+#[derive(Clone, Debug)]
+pub struct BaseImplicit {
+    /// ID of the implicit mod.
+    mod_id: ModId,
+    /// Inclusive ranges of the possible mod values.
+    ranges: Vec<(ModValue, ModValue)>,
+}
+
+/// Base stats of a weapon (without quality or mods).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BaseWeaponStats {
+    /// Range of physical damage of a single hit.
+    pub physical_damage: (u32, u32),
+    /// Number of attacks per second.
+    pub attacks_per_second: f64,
+    /// Critical strike chance, in percent.
+    pub critical_chance: f64,
+    /// Weapon range.
+    pub range: u32,
+}
+
+impl BaseInfo {
+    /// Name of the base type, e.g. "Vaal Regalia".
+    #[inline]
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    /// Item class of the base, e.g. "Body Armour" or "One Hand Sword".
+    #[inline]
+    pub fn class(&self) -> &str {
+        self.class
+    }
+
+    /// Category of items of this base.
+    #[inline]
+    pub fn category(&self) -> ItemCategory {
+        self.category
+    }
+
+    /// Minimum level of the area that items of this base can drop in, if known.
+    #[inline]
+    pub fn drop_level(&self) -> Option<u64> {
+        self.level
+    }
+
+    /// Dimensions of the item (`(width, height)`) in inventory tiles, if known.
+    #[inline]
+    pub fn size(&self) -> Option<(u64, u64)> {
+        self.size
+    }
+
+    /// Requirements for using items of this base, disregarding their mods.
+    #[inline]
+    pub fn requirements(&self) -> &HashMap<Requirement, u32> {
+        &self.requirements
+    }
+
+    /// Implicit mods that items of this base have by default, if known.
+    ///
+    /// Without the `mods_db` feature, only bases with no implicits have them known.
+    #[inline]
+    pub fn implicits(&self) -> Option<&[BaseImplicit]> {
+        self.implicits.as_ref().map(|i| i.as_slice())
+    }
+
+    /// Base defences of armour items, if known.
+    #[inline]
+    pub fn defences(&self) -> Option<Defences> {
+        self.defences
+    }
+
+    /// Base stats of weapons, if known.
+    #[inline]
+    pub fn weapon(&self) -> Option<BaseWeaponStats> {
+        self.weapon
+    }
+}
+
+impl BaseImplicit {
+    /// ID of the implicit mod.
+    #[inline]
+    pub fn mod_id(&self) -> &ModId {
+        &self.mod_id
+    }
+
+    /// Inclusive ranges of values that the implicit mod can roll with,
+    /// one for every mod value.
+    #[inline]
+    pub fn ranges(&self) -> &[(ModValue, ModValue)] {
+        self.ranges.as_slice()
+    }
+
+    /// Whether given mod values could have been rolled for this implicit.
+    #[cfg(feature = "mods_db")]
+    pub fn contains(&self, values: &ModValues) -> bool {
+        values.len() == self.ranges.len()
+            && values.iter().zip(&self.ranges).all(|(v, &(min, max))| min <= v && v <= max)
+    }
+}
+
+
+impl Item {
+    /// Information about the base type of this item, if it's a known one.
+    #[inline]
+    pub fn base_info(&self) -> Option<&'static BaseInfo> {
+        base_info(&self.base)
+    }
+
+    /// Check whether the implicit mods of this item
+    /// are the ones its base type comes with by default.
+    ///
+    /// Returns `None` if this cannot be determined,
+    /// e.g. because the item isn't gear, its base (or its implicits) are unknown,
+    /// or it is corrupted (and thus may have had its implicits changed).
+    ///
+    /// Synthesised items never have the implicits of their base.
+    #[cfg(feature = "mods_db")]
+    pub fn implicits_match_base(&self) -> Option<bool> {
+        if self.corrupted {
+            return None;
+        }
+        if self.synthesised {
+            return Some(false);
+        }
+        let implicit = &self.as_gear()?.implicit;
+        let base_implicits = self.base_info()?.implicits()?;
+        if implicit.len() != base_implicits.len() {
+            return Some(false);
+        }
+        for (m, bi) in implicit.iter().zip(base_implicits) {
+            let (info, values) = match (m.info(), m.values()) {
+                (Some(info), Some(values)) => (info, values),
+                _ => return None,
+            };
+            if info.id() != bi.mod_id() || !bi.contains(values) {
+                return Some(false);
+            }
+        }
+        Some(true)
+    }
+}
+
+
+/// Look up the information about an item base type of given name.
+#[inline]
+pub fn base_info(name: &str) -> Option<&'static BaseInfo> {
+    BASES.get(name)
+}
+
+/// Whether given name is that of a known item base type,
+/// e.g. "Vaal Regalia" or "Academy Map".
+#[inline]
+pub fn is_base_type(name: &str) -> bool {
+    BASES.contains_key(name)
+}
+
+/// Split the full name of a magic item (e.g. "Virile Vaal Regalia of the Titan")
+/// into the names of its prefix and suffix, and its base type.
+///
+/// The base type is found using the list of known item bases.
+/// If it's not there, the known affix names are used (with the `mods_db` feature).
+///
+/// Returns `None` if the name couldn't be split.
+pub fn split_magic_name(name: &str) -> Option<(Option<&str>, &str, Option<&str>)> {
+    let name = name.trim();
+    let known_base = word_spans(name)
+        .filter(|&(start, end)| BASES.contains_key(&name[start..end]))
+        .max_by_key(|&(start, end)| end - start);
+    if let Some((start, end)) = known_base {
+        return Some((non_empty(&name[..start]), &name[start..end], non_empty(&name[end..])));
+    }
+    split_by_affix_names(name)
+}
+
+#[cfg(feature = "mods_db")]
+fn split_by_affix_names(name: &str) -> Option<(Option<&str>, &str, Option<&str>)> {
+    let prefix = affix_names(AffixType::Prefix)
+        .filter(|p| name.starts_with(p) && name[p.len()..].starts_with(' '))
+        .max_by_key(|p| p.len());
+    let suffix = affix_names(AffixType::Suffix)
+        .filter(|s| name.ends_with(s) && name[..name.len() - s.len()].ends_with(' '))
+        .max_by_key(|s| s.len());
+    if prefix.is_none() && suffix.is_none() {
+        return None;
+    }
+    let start = prefix.map(|p| p.len()).unwrap_or(0);
+    let end = name.len() - suffix.map(|s| s.len()).unwrap_or(0);
+    if start >= end {
+        return None;
+    }
+    non_empty(&name[start..end]).map(|base| (prefix, base, suffix))
+}
+
+#[cfg(not(feature = "mods_db"))]
+fn split_by_affix_names(_: &str) -> Option<(Option<&str>, &str, Option<&str>)> {
+    None
+}
+
+
+/// Iterate over the byte spans of all runs of consecutive whole words within a text.
+fn word_spans<'t>(text: &'t str) -> Box<Iterator<Item=(usize, usize)> + 't> {
+    let starts = Some(0).into_iter().chain(text.match_indices(' ').map(|(i, _)| i + 1));
+    Box::new(starts.flat_map(move |start| {
+        text[start..].match_indices(' ').map(move |(i, _)| start + i)
+            .chain(Some(text.len()))
+            .filter(move |&end| end > start)
+            .map(move |end| (start, end))
+    }))
+}
+
+fn non_empty(s: &str) -> Option<&str> {
+    let s = s.trim();
+    if s.is_empty() { None } else { Some(s) }
+}
+
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    #[cfg(feature = "mods_db")]
+    use serde_json::from_value;
+    #[cfg(feature = "mods_db")]
+    use ::stashes::Item;
+    use super::super::{ArmourType, Defences, ItemCategory, Requirement, WeaponType};
+    use super::{base_info, is_base_type, split_magic_name};
+
+    #[test]
+    fn base_info__armour() {
+        let info = base_info("Vaal Regalia").unwrap();
+        assert_eq!("Body Armour", info.class());
+        assert_eq!(ItemCategory::Armour(ArmourType::Chest), info.category());
+        assert_eq!(Some(68), info.drop_level());
+        assert_eq!(Some((2, 3)), info.size());
+        assert_eq!(Some(&194), info.requirements().get(&Requirement::Intelligence));
+        assert_eq!(Some(0), info.implicits().map(|i| i.len()));
+        assert_eq!(Some(Defences{armour: 0, evasion: 0, energy_shield: 175}), info.defences());
+        assert!(info.weapon().is_none());
+    }
+
+    #[test]
+    fn base_info__weapon() {
+        let info = base_info("Imperial Claw").unwrap();
+        assert_eq!(ItemCategory::Weapon(WeaponType::Claw), info.category());
+        assert_eq!(Some((2, 2)), info.size());
+        let weapon = info.weapon().unwrap();
+        assert!(weapon.physical_damage.0 < weapon.physical_damage.1);
+        assert!(weapon.attacks_per_second > 1.0);
+        assert!(info.implicits().is_none());
+    }
+
+    #[test]
+    fn base_info__map() {
+        let info = base_info("Academy Map").unwrap();
+        assert_eq!(ItemCategory::Map, info.category());
+        assert_eq!(Some((1, 1)), info.size());
+        assert!(base_info("Vaal").is_none());
+    }
+
+    #[test]
+    fn base_types() {
+        assert!(is_base_type("Vaal Regalia"));
+        assert!(is_base_type("Academy Map"));
+        assert!(!is_base_type("Vaal"));
+    }
+
+    #[cfg(feature = "mods_db")]
+    #[test]
+    fn implicits_match_base__same() {
+        let item: Item = from_value(json!({
+            "id": "0123456789abcdef",
+            "name": "Foo Bar", "typeLine": "Coral Ring",
+            "ilvl": 84, "frameType": 2, "identified": true,
+            "category": {"accessories": ["ring"]},
+            "implicitMods": ["+25 to maximum Life"], "explicitMods": ["+20 to Strength"],
+            "icon": "", "league": "Standard", "verified": false, "w": 1, "h": 1
+        })).unwrap();
+        assert_eq!(Some(true), item.implicits_match_base());
+    }
+
+    #[cfg(feature = "mods_db")]
+    #[test]
+    fn implicits_match_base__different_values() {
+        let item: Item = from_value(json!({
+            "id": "0123456789abcdef",
+            "name": "Foo Bar", "typeLine": "Coral Ring",
+            "ilvl": 84, "frameType": 2, "identified": true,
+            "category": {"accessories": ["ring"]},
+            "implicitMods": ["+35 to maximum Life"], "explicitMods": ["+20 to Strength"],
+            "icon": "", "league": "Standard", "verified": false, "w": 1, "h": 1
+        })).unwrap();
+        assert_eq!(Some(false), item.implicits_match_base());
+    }
+
+    #[cfg(feature = "mods_db")]
+    #[test]
+    fn implicits_match_base__missing() {
+        let item: Item = from_value(json!({
+            "id": "0123456789abcdef",
+            "name": "Foo Bar", "typeLine": "Coral Ring",
+            "ilvl": 84, "frameType": 2, "identified": true,
+            "category": {"accessories": ["ring"]},
+            "implicitMods": [], "explicitMods": ["+20 to Strength"],
+            "icon": "", "league": "Standard", "verified": false, "w": 1, "h": 1
+        })).unwrap();
+        assert_eq!(Some(false), item.implicits_match_base());
+    }
+
+    #[cfg(feature = "mods_db")]
+    #[test]
+    fn implicits_match_base__corrupted() {
+        let item: Item = from_value(json!({
+            "id": "0123456789abcdef",
+            "name": "Foo Bar", "typeLine": "Coral Ring",
+            "ilvl": 84, "frameType": 2, "identified": true, "corrupted": true,
+            "category": {"accessories": ["ring"]},
+            "implicitMods": ["+35 to maximum Life"], "explicitMods": ["+20 to Strength"],
+            "icon": "", "league": "Standard", "verified": false, "w": 1, "h": 1
+        })).unwrap();
+        assert_eq!(None, item.implicits_match_base());
+    }
+
+    #[test]
+    fn split_magic_names() {
+        assert_eq!(Some((Some("Virile"), "Vaal Regalia", Some("of the Titan"))),
+                   split_magic_name("Virile Vaal Regalia of the Titan"));
+        assert_eq!(Some((None, "Iron Ring", Some("of the Seal"))),
+                   split_magic_name("Iron Ring of the Seal"));
+        assert_eq!(Some((Some("Hexproof"), "Museum Map", Some("of Giants"))),
+                   split_magic_name("Hexproof Museum Map of Giants"));
+        // "Ring" alone mustn't be found within e.g. "Ringmail".
+        assert_eq!(Some((Some("Stout"), "Ringmail Gloves", None)),
+                   split_magic_name("Stout Ringmail Gloves"));
+    }
+}
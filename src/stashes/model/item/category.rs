@@ -21,6 +21,12 @@ pub enum ItemCategory {
     Flask,
     /// A map item.
     Map,
+    /// Map fragment, like "Sacrifice at Dusk" or "Chayula's Breachstone".
+    ///
+    /// These are placed in the map device in order to open special areas.
+    Fragment,
+    /// Scarab that is placed in the map device to modify a map.
+    Scarab,
     /// Skill gem.
     Gem,
     /// Divination card.
@@ -28,7 +34,25 @@ pub enum ItemCategory {
     /// Sealed prophecy item.
     Prophecy,
     /// Relic item.
+    ///
+    /// The category of its base item can be found through `Item::base_info`.
     Relic,
+    /// Essence, used to craft items with a guaranteed mod.
+    Essence,
+    /// Fossil, used in resonators to craft items.
+    Fossil,
+    /// Resonator which has fossils socketed into it.
+    Resonator,
+    /// Leaguestone which modifies the areas of a league.
+    Leaguestone,
+    /// Captured beast.
+    Beast,
+    /// Net used to capture beasts.
+    Net,
+    /// Incubator which adds an item drop to an item.
+    Incubator,
+    /// Oil, used to anoint amulets or enchant items.
+    Oil,
     /// Currency item.
     Currency,  // NOTE: Keep it as last variant.
 }
@@ -41,8 +65,19 @@ impl ItemCategory {
     pub fn is_consumable(&self) -> bool {
         match *self {
             ItemCategory::Map |
+            ItemCategory::Fragment |
+            ItemCategory::Scarab |
             ItemCategory::DivinationCard |
-            ItemCategory::Currency => true, // TODO: prophecy
+            ItemCategory::Prophecy |
+            ItemCategory::Essence |
+            ItemCategory::Fossil |
+            ItemCategory::Resonator |
+            ItemCategory::Leaguestone |
+            ItemCategory::Beast |
+            ItemCategory::Net |
+            ItemCategory::Incubator |
+            ItemCategory::Oil |
+            ItemCategory::Currency => true,
             _ => false,
         }
     }
@@ -72,10 +107,76 @@ impl ItemCategory {
             ItemCategory::Jewel(..) |
             ItemCategory::Flask |
             ItemCategory::Map |
-            ItemCategory::Gem => true,
+            ItemCategory::Gem |
+            ItemCategory::Leaguestone => true,
             _ => false,
         }
     }
+
+    /// All the inventory slots that an item of this category can be put in.
+    ///
+    /// This is empty for items which aren't worn in an inventory slot,
+    /// including jewels and gems (which go into the passive tree or sockets).
+    pub fn slots(&self) -> &'static [Slot] {
+        const ONE_HANDED: &[Slot] = &[Slot::MainHand, Slot::OffHand];
+        const RINGS: &[Slot] = &[Slot::Ring1, Slot::Ring2];
+        const FLASKS: &[Slot] = &[
+            Slot::Flask1, Slot::Flask2, Slot::Flask3, Slot::Flask4, Slot::Flask5,
+        ];
+        match *self {
+            ItemCategory::Accessory(AccessoryType::Amulet) => &[Slot::Amulet],
+            ItemCategory::Accessory(AccessoryType::Belt) => &[Slot::Belt],
+            ItemCategory::Accessory(AccessoryType::Ring) => RINGS,
+            ItemCategory::Armour(ArmourType::Helmet) => &[Slot::Helmet],
+            ItemCategory::Armour(ArmourType::Gloves) => &[Slot::Gloves],
+            ItemCategory::Armour(ArmourType::Chest) => &[Slot::BodyArmour],
+            ItemCategory::Armour(ArmourType::Boots) => &[Slot::Boots],
+            ItemCategory::Armour(ArmourType::Shield) |
+            ItemCategory::Armour(ArmourType::Quiver) => &[Slot::OffHand],
+            ItemCategory::Weapon(wt) => match wt.handedness() {
+                Handedness::OneHanded => ONE_HANDED,
+                Handedness::TwoHanded => &[Slot::MainHand],
+            },
+            ItemCategory::Flask => FLASKS,
+            _ => &[],
+        }
+    }
+}
+
+
+/// Inventory slot of a character where an item can be worn.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Slot {
+    /// Head slot.
+    Helmet,
+    /// Body armour slot.
+    BodyArmour,
+    /// Hands slot.
+    Gloves,
+    /// Feet slot.
+    Boots,
+    /// Belt slot.
+    Belt,
+    /// Amulet slot.
+    Amulet,
+    /// The first (left) ring slot.
+    Ring1,
+    /// The second (right) ring slot.
+    Ring2,
+    /// Main hand (weapon) slot.
+    MainHand,
+    /// Off hand slot, for shields, quivers, and one-handed weapons when dual wielding.
+    OffHand,
+    /// The first flask slot.
+    Flask1,
+    /// The second flask slot.
+    Flask2,
+    /// The third flask slot.
+    Flask3,
+    /// The fourth flask slot.
+    Flask4,
+    /// The fifth flask slot.
+    Flask5,
 }
 
 
@@ -108,7 +209,6 @@ pub enum ArmourType {
     /// A quiver.
     Quiver,
 }
-// TODO: consider introducing a Slot enum (to lump together quivers and shields)
 
 /// Type of a weapon.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
@@ -138,8 +238,87 @@ pub enum WeaponType {
     /// Wand.
     Wand,
 }
-// TODO: consider introducing weapon "kind" (mace/staff/sword/axe) that lumps 1H & 2H together;
-// bear in mind that "handedness" is ambiguous for bows, though
+
+impl WeaponType {
+    /// Kind of the weapon, regardless of whether it's one- or two-handed.
+    pub fn kind(&self) -> WeaponKind {
+        match *self {
+            WeaponType::Bow => WeaponKind::Bow,
+            WeaponType::Claw => WeaponKind::Claw,
+            WeaponType::Dagger => WeaponKind::Dagger,
+            WeaponType::OneHandedAxe | WeaponType::TwoHandedAxe => WeaponKind::Axe,
+            WeaponType::OneHandedMace | WeaponType::TwoHandedMace |
+            WeaponType::Sceptre => WeaponKind::Mace,
+            WeaponType::OneHandedSword | WeaponType::TwoHandedSword => WeaponKind::Sword,
+            WeaponType::Staff => WeaponKind::Staff,
+            WeaponType::Wand => WeaponKind::Wand,
+        }
+    }
+
+    /// Whether the weapon is held in one or both hands.
+    ///
+    /// Bows are two-handed, even though they can be used with a quiver in the off hand.
+    pub fn handedness(&self) -> Handedness {
+        match *self {
+            WeaponType::Bow |
+            WeaponType::Staff |
+            WeaponType::TwoHandedAxe |
+            WeaponType::TwoHandedMace |
+            WeaponType::TwoHandedSword => Handedness::TwoHanded,
+            _ => Handedness::OneHanded,
+        }
+    }
+
+    /// Whether this weapon can be dual wielded together with the other one.
+    ///
+    /// Any two one-handed weapons can be dual wielded, even if they are of different kinds.
+    #[inline]
+    pub fn can_dual_wield_with(&self, other: WeaponType) -> bool {
+        self.handedness() == Handedness::OneHanded && other.handedness() == Handedness::OneHanded
+    }
+
+    /// Whether an item of given category can be worn in the off hand
+    /// while this weapon is in the main hand.
+    pub fn allows_off_hand(&self, off_hand: &ItemCategory) -> bool {
+        match *off_hand {
+            ItemCategory::Armour(ArmourType::Quiver) => *self == WeaponType::Bow,
+            ItemCategory::Armour(ArmourType::Shield) =>
+                self.handedness() == Handedness::OneHanded,
+            ItemCategory::Weapon(wt) => self.can_dual_wield_with(wt),
+            _ => false,
+        }
+    }
+}
+
+/// Kind of a weapon, lumping together its one- and two-handed variants.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum WeaponKind {
+    /// A bow.
+    Bow,
+    /// A claw.
+    Claw,
+    /// A dagger.
+    Dagger,
+    /// An axe (one- or two-handed).
+    Axe,
+    /// A mace (one- or two-handed), including sceptres.
+    Mace,
+    /// A sword (one- or two-handed), including thrusting swords.
+    Sword,
+    /// A staff.
+    Staff,
+    /// A wand.
+    Wand,
+}
+
+/// Whether a weapon is held in one hand or both.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Handedness {
+    /// One-handed weapon.
+    OneHanded,
+    /// Two-handed weapon.
+    TwoHanded,
+}
 
 /// Type of a jewel.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
@@ -153,3 +332,37 @@ pub enum JewelType {
     /// These can be placed both in passive tree slots and abyss sockets in gear.
     Abyss
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::{AccessoryType, ArmourType, ItemCategory, Slot, WeaponKind, WeaponType};
+
+    #[test]
+    fn slots() {
+        assert_eq!(&[Slot::Ring1, Slot::Ring2],
+                   ItemCategory::Accessory(AccessoryType::Ring).slots());
+        assert_eq!(&[Slot::OffHand], ItemCategory::Armour(ArmourType::Quiver).slots());
+        assert_eq!(&[Slot::MainHand, Slot::OffHand],
+                   ItemCategory::Weapon(WeaponType::Claw).slots());
+        assert_eq!(&[Slot::MainHand], ItemCategory::Weapon(WeaponType::Bow).slots());
+        assert_eq!(5, ItemCategory::Flask.slots().len());
+        assert!(ItemCategory::Gem.slots().is_empty());
+    }
+
+    #[test]
+    fn weapon_kinds() {
+        assert_eq!(WeaponKind::Axe, WeaponType::TwoHandedAxe.kind());
+        assert_eq!(WeaponKind::Mace, WeaponType::Sceptre.kind());
+    }
+
+    #[test]
+    fn off_hand() {
+        assert!(WeaponType::Claw.can_dual_wield_with(WeaponType::OneHandedSword));
+        assert!(!WeaponType::Claw.can_dual_wield_with(WeaponType::Bow));
+        assert!(WeaponType::Bow.allows_off_hand(&ItemCategory::Armour(ArmourType::Quiver)));
+        assert!(!WeaponType::Bow.allows_off_hand(&ItemCategory::Armour(ArmourType::Shield)));
+        assert!(WeaponType::Wand.allows_off_hand(&ItemCategory::Armour(ArmourType::Shield)));
+        assert!(!WeaponType::Staff.allows_off_hand(&ItemCategory::Weapon(WeaponType::Wand)));
+    }
+}
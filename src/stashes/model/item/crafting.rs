@@ -0,0 +1,515 @@
+//! Calculations of odds & costs of crafting item sockets
+//! (colors, number of sockets, and links).
+//!
+//! Socket colors follow the community model based on item attribute requirements,
+//! as used by Siveran's chromatic calculator (https://siveran.github.io/calc.html).
+//! Chances of rolling sockets & links with Jeweller's Orbs and Orbs of Fusing
+//! aren't published by GGG, so the numbers used here are community estimates.
+
+use std::cmp::Ordering;
+
+use stashes::model::currency::{Currency, CHROME, FUSE, JEW};
+use super::{ArmourType, Color, Item, ItemCategory, Requirement, WeaponType};
+
+
+/// Chances of a single socket on an item rolling a particular color.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorChances {
+    /// Chance of a red socket.
+    pub red: f64,
+    /// Chance of a green socket.
+    pub green: f64,
+    /// Chance of a blue socket.
+    pub blue: f64,
+}
+
+/// Constant (`X` in the calculator's formula) that's added to color weights
+/// of single-attribute items, so that their off-colors can still roll.
+const COLOR_WEIGHT_BONUS: f64 = 12.0;
+/// Maximum chance (`maxOnColorChance` in the calculator's formula)
+/// of a socket matching one of the item's attribute requirements.
+const MAX_ON_COLOR_CHANCE: f64 = 0.9;
+
+impl ColorChances {
+    /// Compute socket color chances for an item with given attribute requirements.
+    ///
+    /// For an item with a single attribute requirement `R`,
+    /// the chance of its color is `0.9 * (X + R) / (R + 3X)`,
+    /// and every off-color gets `0.9 * X / (R + 3X) + 0.05` (with `X = 12`).
+    /// Items with two requirements split 90% between their colors proportionally
+    /// and leave 10% for the third one, while tri-attribute items are fully proportional.
+    pub fn for_requirements(strength: u32, dexterity: u32, intelligence: u32) -> Self {
+        let reqs = [strength as f64, dexterity as f64, intelligence as f64];
+        let total: f64 = reqs.iter().sum();
+        let chances: Vec<f64> = match reqs.iter().filter(|&&r| r > 0.0).count() {
+            // Items without requirements roll all colors with equal chance.
+            0 => vec![1.0 / 3.0; 3],
+            // Single-attribute items are likely to roll their color,
+            // but there is a chance for off-colors, too.
+            1 => reqs.iter().map(|&r| {
+                let weighted = (COLOR_WEIGHT_BONUS + r) / (total + 3.0 * COLOR_WEIGHT_BONUS);
+                if r > 0.0 {
+                    MAX_ON_COLOR_CHANCE * weighted
+                } else {
+                    MAX_ON_COLOR_CHANCE * weighted + (1.0 - MAX_ON_COLOR_CHANCE) / 2.0
+                }
+            }).collect(),
+            // Dual-attribute items split the on-color chance proportionally
+            // and leave the rest to the third color.
+            2 => reqs.iter().map(|&r| {
+                if r > 0.0 { MAX_ON_COLOR_CHANCE * r / total } else { 1.0 - MAX_ON_COLOR_CHANCE }
+            }).collect(),
+            _ => reqs.iter().map(|&r| r / total).collect(),
+        };
+        ColorChances{red: chances[0], green: chances[1], blue: chances[2]}
+    }
+
+    /// Chance of a single socket rolling given color.
+    ///
+    /// White sockets cannot be rolled, so their chance is always zero.
+    pub fn of(&self, color: Color) -> f64 {
+        match color {
+            Color::Red => self.red,
+            Color::Green => self.green,
+            Color::Blue => self.blue,
+            Color::White => 0.0,
+        }
+    }
+
+    /// Chance that `count` sockets roll at least given numbers of (`red`, `green`, `blue`) sockets.
+    pub fn at_least(&self, count: usize, red: usize, green: usize, blue: usize) -> f64 {
+        let mut result = 0.0;
+        for r in red..(count + 1) {
+            for g in green..(count - r + 1) {
+                let b = count - r - g;
+                if b >= blue {
+                    result += self.exactly(r, g, b);
+                }
+            }
+        }
+        result
+    }
+
+    /// Chance that sockets roll one particular arrangement of colors,
+    /// with given numbers of (`red`, `green`, `blue`) sockets at given positions.
+    pub fn arrangement(&self, red: usize, green: usize, blue: usize) -> f64 {
+        self.red.powi(red as i32) * self.green.powi(green as i32) * self.blue.powi(blue as i32)
+    }
+
+    /// Chance that sockets roll exactly given numbers of (`red`, `green`, `blue`) sockets,
+    /// in any arrangement.
+    pub fn exactly(&self, red: usize, green: usize, blue: usize) -> f64 {
+        let arrangements = factorial(red + green + blue)
+            / (factorial(red) * factorial(green) * factorial(blue));
+        arrangements * self.arrangement(red, green, blue)
+    }
+}
+
+
+/// A way of crafting item sockets, together with its cost and odds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CraftingOption {
+    /// How the crafting is done.
+    pub method: CraftingMethod,
+    /// Currency used for crafting.
+    pub currency: Currency,
+    /// Amount of `currency` spent on a single attempt.
+    pub cost: u32,
+    /// Chance of success on a single attempt.
+    pub chance: f64,
+}
+
+impl CraftingOption {
+    /// Average number of attempts needed to succeed.
+    #[inline]
+    pub fn expected_attempts(&self) -> f64 {
+        1.0 / self.chance
+    }
+
+    /// Average amount of `currency` needed to succeed.
+    #[inline]
+    pub fn expected_cost(&self) -> f64 {
+        self.cost as f64 / self.chance
+    }
+}
+
+/// Method of crafting item sockets.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CraftingMethod {
+    /// Using currency orbs directly on the item.
+    Currency,
+    /// Crafting bench recipe forcing sockets of given colors
+    /// (while the rest of sockets are rolled randomly).
+    BenchColors(Vec<Color>),
+    /// Crafting bench recipe setting given number of sockets.
+    BenchSockets(usize),
+    /// Crafting bench recipe linking given number of sockets.
+    BenchLinks(usize),
+}
+
+
+/// Crafting bench recipes for socket colors, as (forced colors, chromatic orb cost).
+const BENCH_COLORS: &[(&[Color], u32)] = &[
+    (&[Color::Red], 4), (&[Color::Green], 4), (&[Color::Blue], 4),
+    (&[Color::Red, Color::Red], 25), (&[Color::Green, Color::Green], 25),
+    (&[Color::Blue, Color::Blue], 25),
+    (&[Color::Red, Color::Red, Color::Red], 120), (&[Color::Green, Color::Green, Color::Green], 120),
+    (&[Color::Blue, Color::Blue, Color::Blue], 120),
+    (&[Color::Red, Color::Green], 15), (&[Color::Red, Color::Blue], 15),
+    (&[Color::Green, Color::Blue], 15),
+    (&[Color::Red, Color::Red, Color::Green], 100), (&[Color::Red, Color::Red, Color::Blue], 100),
+    (&[Color::Green, Color::Green, Color::Red], 100), (&[Color::Green, Color::Green, Color::Blue], 100),
+    (&[Color::Blue, Color::Blue, Color::Red], 100), (&[Color::Blue, Color::Blue, Color::Green], 100),
+];
+
+/// Crafting bench costs (in Jeweller's Orbs) of setting the number of sockets,
+/// indexed by that number.
+const BENCH_SOCKETS: &[u32] = &[0, 0, 1, 3, 10, 70, 350];
+
+/// Crafting bench costs (in Orbs of Fusing) of linking sockets,
+/// indexed by the number of links.
+const BENCH_LINKS: &[u32] = &[0, 0, 1, 3, 5, 150, 1500];
+
+/// Estimated chances of rolling at least given number of sockets with a Jeweller's Orb
+/// (on an item that can have that many), indexed by the number of sockets.
+const JEWELLER_CHANCES: &[f64] = &[1.0, 1.0, 0.8, 0.5, 0.25, 1.0 / 20.0, 1.0 / 150.0];
+
+/// Estimated chances of linking given number of sockets with an Orb of Fusing,
+/// indexed by the number of links.
+const FUSING_CHANCES: &[f64] = &[1.0, 1.0, 0.5, 0.25, 0.1, 1.0 / 100.0, 1.0 / 1200.0];
+
+
+impl Item {
+    /// Chances of a single socket on this item rolling a particular color,
+    /// based on item's attribute requirements.
+    pub fn socket_color_chances(&self) -> ColorChances {
+        let req = |r| self.requirements.get(&r).cloned().unwrap_or(0);
+        ColorChances::for_requirements(
+            req(Requirement::Strength), req(Requirement::Dexterity), req(Requirement::Intelligence))
+    }
+
+    /// Maximum number of sockets this item can have,
+    /// based on its category and item level.
+    pub fn max_sockets(&self) -> usize {
+        let by_category = match self.category.as_ref() {
+            Some(&ItemCategory::Armour(ArmourType::Chest)) => 6,
+            Some(&ItemCategory::Armour(ArmourType::Helmet)) |
+            Some(&ItemCategory::Armour(ArmourType::Gloves)) |
+            Some(&ItemCategory::Armour(ArmourType::Boots)) => 4,
+            Some(&ItemCategory::Armour(ArmourType::Shield)) => 3,
+            Some(&ItemCategory::Weapon(ref wt)) => match *wt {
+                WeaponType::Bow | WeaponType::Staff |
+                WeaponType::TwoHandedAxe | WeaponType::TwoHandedMace |
+                WeaponType::TwoHandedSword => 6,
+                _ => 3,
+            },
+            _ => 0,
+        };
+        let by_level = match self.level {
+            0..=1 => 2,
+            2..=24 => 3,
+            25..=34 => 4,
+            35..=49 => 5,
+            _ => 6,
+        };
+        by_category.min(by_level)
+    }
+
+    /// Ways of getting at least given socket colors on this item,
+    /// sorted by their expected cost (cheapest first).
+    ///
+    /// White colors in `target` stand for sockets of any color.
+    /// White sockets of the item itself are never recolored, so they only count towards those.
+    /// The result is empty if the item doesn't have enough sockets.
+    pub fn chromatic_options(&self, target: &[Color]) -> Vec<CraftingOption> {
+        let count = self.sockets.regular_count() as usize;
+        let rollable = count - self.sockets.count_of(Color::White);
+        let chances = self.socket_color_chances();
+        let needed = |colors: &[Color], c| colors.iter().filter(|&&x| x == c).count();
+        let (red, green, blue) = (
+            needed(target, Color::Red), needed(target, Color::Green), needed(target, Color::Blue));
+        if target.len() > count || red + green + blue > rollable {
+            return vec![];
+        }
+
+        let mut result = Vec::new();
+
+        // Chromatic Orbs never give the exact arrangement of colors that the item already has,
+        // so the odds are conditional on the outcome being any other arrangement.
+        let (current_red, current_green, current_blue) = (
+            self.sockets.count_of(Color::Red),
+            self.sockets.count_of(Color::Green),
+            self.sockets.count_of(Color::Blue));
+        let current_chance = chances.arrangement(current_red, current_green, current_blue);
+        let current_succeeds = current_red >= red && current_green >= green && current_blue >= blue;
+        if current_chance < 1.0 {
+            let success_chance = chances.at_least(rollable, red, green, blue)
+                - if current_succeeds { current_chance } else { 0.0 };
+            result.push(CraftingOption{
+                method: CraftingMethod::Currency,
+                currency: CHROME,
+                cost: 1,
+                chance: success_chance / (1.0 - current_chance),
+            });
+        }
+
+        for &(forced, cost) in BENCH_COLORS {
+            let (forced_red, forced_green, forced_blue) = (
+                needed(forced, Color::Red), needed(forced, Color::Green), needed(forced, Color::Blue));
+            if forced_red > red || forced_green > green || forced_blue > blue {
+                continue;  // recipe forces colors that we don't want
+            }
+            result.push(CraftingOption{
+                method: CraftingMethod::BenchColors(forced.to_vec()),
+                currency: CHROME,
+                cost,
+                chance: chances.at_least(rollable - forced.len(),
+                    red - forced_red, green - forced_green, blue - forced_blue),
+            });
+        }
+
+        sort_by_expected_cost(result)
+    }
+
+    /// Ways of getting at least given number of sockets on this item,
+    /// sorted by their expected cost (cheapest first).
+    ///
+    /// The result is empty if the item cannot have that many sockets.
+    pub fn jeweller_options(&self, sockets: usize) -> Vec<CraftingOption> {
+        if sockets > self.max_sockets() {
+            return vec![];
+        }
+        let mut result = vec![CraftingOption{
+            method: CraftingMethod::Currency,
+            currency: JEW,
+            cost: 1,
+            chance: JEWELLER_CHANCES[sockets],
+        }];
+        if BENCH_SOCKETS[sockets] > 0 {
+            result.push(CraftingOption{
+                method: CraftingMethod::BenchSockets(sockets),
+                currency: JEW,
+                cost: BENCH_SOCKETS[sockets],
+                chance: 1.0,
+            });
+        }
+        sort_by_expected_cost(result)
+    }
+
+    /// Ways of getting at least given number of linked sockets on this item,
+    /// sorted by their expected cost (cheapest first).
+    ///
+    /// The result is empty if the item doesn't have enough sockets,
+    /// or if it cannot have that many to begin with.
+    pub fn fusing_options(&self, links: usize) -> Vec<CraftingOption> {
+        if links > self.sockets.regular_count() as usize || links > self.max_sockets() {
+            return vec![];
+        }
+        let mut result = vec![CraftingOption{
+            method: CraftingMethod::Currency,
+            currency: FUSE,
+            cost: 1,
+            chance: FUSING_CHANCES[links],
+        }];
+        if BENCH_LINKS[links] > 0 {
+            result.push(CraftingOption{
+                method: CraftingMethod::BenchLinks(links),
+                currency: FUSE,
+                cost: BENCH_LINKS[links],
+                chance: 1.0,
+            });
+        }
+        sort_by_expected_cost(result)
+    }
+}
+
+
+// Utility functions
+
+fn sort_by_expected_cost(mut options: Vec<CraftingOption>) -> Vec<CraftingOption> {
+    options.retain(|o| o.chance > 0.0);
+    options.sort_by(|a, b| {
+        a.expected_cost().partial_cmp(&b.expected_cost()).unwrap_or(Ordering::Equal)
+    });
+    options
+}
+
+fn factorial(n: usize) -> f64 {
+    (1..n + 1).map(|i| i as f64).product()
+}
+
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use serde_json::from_value;
+    use ::stashes::{Color, Item};
+    use super::{ColorChances, CraftingMethod};
+
+    #[test]
+    fn color_chances_sum_to_one() {
+        for &(s, d, i) in &[(0, 0, 0), (100, 0, 0), (0, 50, 70), (30, 40, 50)] {
+            let cc = ColorChances::for_requirements(s, d, i);
+            assert!((cc.red + cc.green + cc.blue - 1.0).abs() < 1e-9);
+            assert!((cc.at_least(4, 0, 0, 0) - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn color_chances_favor_requirements() {
+        let cc = ColorChances::for_requirements(0, 0, 155);
+        assert!(cc.blue > cc.red);
+        assert_eq!(cc.red, cc.green);
+        assert_eq!(0.0, cc.of(Color::White));
+    }
+
+    fn chromatic_chance(item: &Item, target: &[Color]) -> f64 {
+        item.chromatic_options(target).into_iter()
+            .find(|o| o.method == CraftingMethod::Currency)
+            .map(|o| o.chance).unwrap()
+    }
+
+    #[test]
+    fn color_chances_reference() {
+        // 180 Strength: 0.9 * (12 + 180) / (180 + 36) = 0.8 for red,
+        // 0.9 * 12 / (180 + 36) + 0.05 = 0.1 for either off-color.
+        let cc = ColorChances::for_requirements(180, 0, 0);
+        assert!((cc.red - 0.8).abs() < 1e-9);
+        assert!((cc.green - 0.1).abs() < 1e-9 && (cc.blue - 0.1).abs() < 1e-9);
+        // Two requirements: 90% split proportionally, 10% for the off-color.
+        let cc = ColorChances::for_requirements(100, 0, 50);
+        assert!((cc.red - 0.6).abs() < 1e-9);
+        assert!((cc.blue - 0.3).abs() < 1e-9);
+        assert!((cc.green - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn chromatic_chance_reference__all_off_color() {
+        let item: Item = from_value(json!({
+            "id": "0123456789abcdef",
+            "name": "", "typeLine": "Astral Plate",
+            "ilvl": 84, "frameType": 0, "identified": true,
+            "category": {"armour": ["chest"]},
+            "requirements": [
+                {"name": "Level", "values": [["62", 0]], "displayMode": 0},
+                {"name": "Str", "values": [["180", 0]], "displayMode": 1}
+            ],
+            "sockets": [
+                {"group": 0, "attr": "S", "sColour": "R"},
+                {"group": 0, "attr": "S", "sColour": "R"},
+                {"group": 0, "attr": "S", "sColour": "R"}
+            ],
+            "icon": "", "league": "Standard", "verified": false, "w": 2, "h": 3
+        })).unwrap();
+        // P(RGB in any order) = 6 * 0.8 * 0.1 * 0.1 = 0.048,
+        // conditioned on not rolling RRR again (0.8^3 = 0.512).
+        let rgb = &[Color::Red, Color::Green, Color::Blue];
+        assert!((chromatic_chance(&item, rgb) - 0.048 / 0.488).abs() < 1e-9);
+    }
+
+    #[test]
+    fn chromatic_chance_reference__current_arrangement() {
+        let item: Item = from_value(json!({
+            "id": "0123456789abcdef",
+            "name": "", "typeLine": "Astral Plate",
+            "ilvl": 84, "frameType": 0, "identified": true,
+            "category": {"armour": ["chest"]},
+            "requirements": [
+                {"name": "Level", "values": [["62", 0]], "displayMode": 0},
+                {"name": "Str", "values": [["180", 0]], "displayMode": 1}
+            ],
+            "sockets": [
+                {"group": 0, "attr": "S", "sColour": "R"},
+                {"group": 0, "attr": "S", "sColour": "G"},
+                {"group": 0, "attr": "S", "sColour": "B"}
+            ],
+            "icon": "", "league": "Standard", "verified": false, "w": 2, "h": 3
+        })).unwrap();
+        // The current arrangement (0.8 * 0.1 * 0.1 = 0.008) is excluded from successes, too.
+        let rgb = &[Color::Red, Color::Green, Color::Blue];
+        assert!((chromatic_chance(&item, rgb) - 0.040 / 0.992).abs() < 1e-9);
+    }
+
+    #[test]
+    fn chromatic_chance_reference__white_sockets() {
+        let item: Item = from_value(json!({
+            "id": "0123456789abcdef",
+            "name": "", "typeLine": "Astral Plate",
+            "ilvl": 84, "frameType": 0, "identified": true,
+            "category": {"armour": ["chest"]},
+            "requirements": [
+                {"name": "Level", "values": [["62", 0]], "displayMode": 0},
+                {"name": "Str", "values": [["180", 0]], "displayMode": 1}
+            ],
+            "sockets": [
+                {"group": 0, "attr": "S", "sColour": "R"},
+                {"group": 0, "attr": "S", "sColour": "R"},
+                {"group": 0, "attr": "S", "sColour": "W"}
+            ],
+            "icon": "", "league": "Standard", "verified": false, "w": 2, "h": 3
+        })).unwrap();
+        // White sockets are never recolored.
+        assert!((chromatic_chance(&item, &[Color::Green, Color::White])
+            - (1.0 - 0.9 * 0.9) / (1.0 - 0.64)).abs() < 1e-9);
+        assert!(item.chromatic_options(&[Color::Red, Color::Green, Color::Blue]).is_empty());
+    }
+
+    #[test]
+    fn crafting_options() {
+        let item: Item = from_value(json!({
+            "id": "0123456789abcdef",
+            "name": "", "typeLine": "Vaal Regalia",
+            "ilvl": 84, "frameType": 2, "identified": true,
+            "category": {"armour": ["chest"]},
+            "requirements": [
+                {"name": "Level", "values": [["68", 0]], "displayMode": 0},
+                {"name": "Int", "values": [["194", 0]], "displayMode": 1}
+            ],
+            "sockets": [
+                {"group": 0, "attr": "I", "sColour": "B"},
+                {"group": 0, "attr": "I", "sColour": "B"},
+                {"group": 1, "attr": "I", "sColour": "B"},
+                {"group": 1, "attr": "I", "sColour": "B"}
+            ],
+            "icon": "", "league": "Standard", "verified": false, "w": 2, "h": 3
+        })).unwrap();
+        assert_eq!(6, item.max_sockets());
+
+        let options = item.chromatic_options(&[Color::Red, Color::Red, Color::Red]);
+        assert!(!options.is_empty());
+        let cheapest = &options[0];
+        assert_eq!(CraftingMethod::BenchColors(vec![Color::Red, Color::Red, Color::Red]),
+                   cheapest.method);
+        assert_eq!(1.0, cheapest.chance);
+        assert!(item.chromatic_options(&[Color::Red; 5]).is_empty());
+
+        assert_eq!(2, item.jeweller_options(6).len());
+        assert!(item.jeweller_options(7).is_empty());
+        let fusing = item.fusing_options(4);
+        assert_eq!(CraftingMethod::BenchLinks(4), fusing[0].method);
+        assert!(item.fusing_options(5).is_empty());
+    }
+
+    #[test]
+    fn fusing_options_beyond_max_sockets() {
+        let item: Item = from_value(json!({
+            "id": "0123456789abcdef",
+            "name": "", "typeLine": "Astral Plate",
+            "ilvl": 84, "frameType": 0, "identified": true,
+            "category": {"armour": ["chest"]},
+            "sockets": [
+                {"group": 0, "attr": "S", "sColour": "R"},
+                {"group": 0, "attr": "S", "sColour": "R"},
+                {"group": 0, "attr": "S", "sColour": "R"},
+                {"group": 0, "attr": "S", "sColour": "R"},
+                {"group": 0, "attr": "S", "sColour": "R"},
+                {"group": 0, "attr": "S", "sColour": "R"},
+                {"group": 0, "attr": "S", "sColour": "R"}
+            ],
+            "icon": "", "league": "Standard", "verified": false, "w": 2, "h": 3
+        })).unwrap();
+        assert_eq!(7, item.sockets.regular_count());
+        assert!(item.fusing_options(7).is_empty());
+        assert!(!item.fusing_options(6).is_empty());
+    }
+}
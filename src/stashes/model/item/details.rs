@@ -1,94 +1,29 @@
 //! Item details which are specific to a particular kind of an item.
 
+use std::collections::HashSet;
 use std::iter;
 use std::time::Duration;
 
 use super::experience::Experience;
+use super::gem::{GemTag, GemVariant};
 use super::mods::Mod;
 
 
 /// Details of the particular items, if known.
 #[derive(Debug)]
 pub enum ItemDetails {
-    // TODO: consider extracting struct types for Map, Gem, Gear
-
     /// An unidentified item. No details available.
     Unidentified,
     /// Map item.
-    Map {
-        /// Map tier.
-        tier: u32,
-        /// Percentage bonus to the quantity of items dropped by this map.
-        item_quantity: i32,
-        /// Percentage bonus to the rarity of items dropped by this map.
-        item_rarity: i32,
-        /// Percentage bonus to the size of monster packs in this map.
-        monster_pack_size: i32,
-        /// Mods this map has.
-        ///
-        /// These are the mods which affect map difficulty
-        /// as well as quantity and rarity of items dropped.
-        mods: Vec<Mod>,
-    },
+    Map(MapDetails),
     /// Skill gem.
-    Gem {
-        /// Current level of the gem.
-        ///
-        /// Gems start at level 1.
-        /// Standalone gems (not in gear) currently shouldn't have levels above 21
-        /// (at least outside of glitches/bugs).
-        level: u32,
-        /// The amount of experience a gem has and requires for the next level.
-        experience: Experience,
-    },
+    Gem(GemDetails),
     /// Flask item.
-    Flask {
-        /// Duration of the flask effect.
-        duration: Duration,
-        /// How many charges are consumed on use.
-        charges_per_use: u32,
-        // TODO: max_charges (which comes from "Consumes %0 of %0 Charges on use"
-        // and requires support for multi-value Properties)
-
-        /// Utility mods of the flask.
-        ///
-        /// These are typically the on-use effect (like bleed/freeze/etc. removal),
-        /// mods affecting charges, etc.
-        mods: Vec<Mod>,
-    },
+    Flask(FlaskDetails),
     /// Item that goes in a gear slot.
     ///
     /// This also includes jewels that go in the passive tree or into abyssal sockets.
-    Gear {
-        /// Implicit mods an item has
-        /// (those displayed in navy color above a horizontal line in the UI).
-        ///
-        /// Currently, PoE only supports a single implicit mod,
-        /// but this may change in the future.
-        implicit: Vec<Mod>,
-
-        /// Enchantments an item has
-        /// (i.e. mods displayed in light blue color above a horizontal line in the UI).
-        ///
-        /// Currently, PoE only supports a single enchantment,
-        /// but this may change in the future.
-        enchants: Vec<Mod>,
-
-        /// Explicit mods of an item
-        /// (those displayed in navy color in the main item pane).
-        ///
-        /// Note that these the mods which are visible in the UI,
-        /// as opposed to *affixes* (prefixes & suffixes) which cannot be reliably
-        /// extrapolated from mods.
-        /// The practical consequence is that there may be more than 6 mods
-        /// which would seemingly contradict the "3 prefixes + 3 suffixes" rule
-        /// (due to the so-called hybrid affixes that result in multiple mods).
-        explicit: Vec<Mod>,
-
-        /// Crafted mods on an item
-        /// (those displayed in light blue color in the main item pane, below explicit mods).
-        crafted: Vec<Mod>,
-    },
+    Gear(GearDetails),
 }
 
 impl ItemDetails {
@@ -105,17 +40,207 @@ impl ItemDetails {
     /// in the top-down order with respect to the in-game UI.
     pub fn mods<'m>(&'m self) -> Box<Iterator<Item=&'m Mod> + 'm> {
         match *self {
-            ItemDetails::Map{ ref mods, .. } |
-            ItemDetails::Flask{ ref mods, .. } => Box::new(mods.iter()),
-            ItemDetails::Gear{
-                ref implicit, ref enchants, ref explicit, ref crafted,
-            } => Box::new(
-                implicit.iter()
-                    .chain(enchants.iter())
-                    .chain(explicit.iter())
-                    .chain(crafted.iter())
-            ),
+            ItemDetails::Map(ref map) => Box::new(map.mods.iter()),
+            ItemDetails::Flask(ref flask) => Box::new(flask.mods.iter()),
+            ItemDetails::Gear(ref gear) => gear.mods(),
             _ => Box::new(iter::empty()),
         }
     }
 }
+
+
+/// Details of a map item.
+#[derive(Debug)]
+pub struct MapDetails {
+    /// Map tier.
+    pub tier: u32,
+    /// Percentage bonus to the quantity of items dropped by this map.
+    pub item_quantity: i32,
+    /// Percentage bonus to the rarity of items dropped by this map.
+    pub item_rarity: i32,
+    /// Percentage bonus to the size of monster packs in this map.
+    pub monster_pack_size: i32,
+    /// Mods this map has.
+    ///
+    /// These are the mods which affect map difficulty
+    /// as well as quantity and rarity of items dropped.
+    pub mods: Vec<Mod>,
+}
+
+/// Details of a skill gem.
+#[derive(Debug)]
+pub struct GemDetails {
+    /// Current level of the gem.
+    ///
+    /// Gems start at level 1.
+    /// Standalone gems (not in gear) currently shouldn't have levels above 21
+    /// (at least outside of glitches/bugs).
+    pub level: u32,
+    /// Whether the gem is at its maximum level,
+    /// i.e. it cannot gain any more through experience.
+    pub max_level: bool,
+    /// Highest level that the gem can reach by gaining experience
+    /// (usually 20).
+    pub level_cap: u32,
+    /// The amount of experience a gem has and requires for the next level.
+    pub experience: Experience,
+    /// Tags of the gem, like Spell or Minion.
+    pub tags: HashSet<GemTag>,
+    /// Whether this is a support gem (as opposed to an active skill gem).
+    pub support: bool,
+    /// Variant of the gem, e.g. Vaal.
+    pub variant: GemVariant,
+}
+
+impl GemDetails {
+    /// Whether the gem is at its maximum level
+    /// and thus won't level up any further by gaining experience.
+    #[inline]
+    pub fn is_max_level(&self) -> bool {
+        self.max_level
+    }
+
+    /// Whether the gem's level is above the one it can reach through experience,
+    /// like in case of "21/20" gems.
+    #[inline]
+    pub fn is_overleveled(&self) -> bool {
+        self.level > self.level_cap
+    }
+
+    /// Whether the gem has given tag.
+    #[inline]
+    pub fn has_tag(&self, tag: GemTag) -> bool {
+        self.tags.contains(&tag)
+    }
+
+    /// Whether this is a support gem.
+    #[inline]
+    pub fn is_support(&self) -> bool {
+        self.support
+    }
+
+    /// Whether this is an active skill gem.
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        !self.support
+    }
+}
+
+/// Details of a flask item.
+#[derive(Debug)]
+pub struct FlaskDetails {
+    /// Duration of the flask effect,
+    /// or of the recovery in case of life & mana flasks.
+    pub duration: Duration,
+    /// How many charges are consumed on use.
+    pub charges_per_use: u32,
+    /// Maximum number of charges the flask can hold, if known.
+    pub max_charges: Option<u32>,
+    /// Mods of the flask.
+    ///
+    /// These are the utility mods which make up the effect of utility flasks,
+    /// followed by the explicit mods of magic & unique flasks
+    /// (like on-use bleed/freeze/etc. removal, mods affecting charges, etc.).
+    pub mods: Vec<Mod>,
+}
+
+impl FlaskDetails {
+    /// How many times can the flask be used in a row
+    /// when it's full of charges.
+    ///
+    /// Returns `None` if the maximum number of charges is unknown.
+    #[inline]
+    pub fn uses(&self) -> Option<u32> {
+        if self.charges_per_use == 0 {
+            return Some(0);
+        }
+        self.max_charges.map(|max| max / self.charges_per_use)
+    }
+}
+
+/// Details of an item that goes in a gear slot.
+#[derive(Debug)]
+pub struct GearDetails {
+    /// Implicit mods an item has
+    /// (those displayed in navy color above a horizontal line in the UI).
+    ///
+    /// Currently, PoE only supports a single implicit mod,
+    /// but this may change in the future.
+    ///
+    /// For synthesised items, these are `ModType::Synthesised` mods.
+    pub implicit: Vec<Mod>,
+
+    /// Enchantments an item has
+    /// (i.e. mods displayed in light blue color above a horizontal line in the UI).
+    ///
+    /// Currently, PoE only supports a single enchantment,
+    /// but this may change in the future.
+    pub enchants: Vec<Mod>,
+
+    /// Fractured mods of an item
+    /// (explicit mods displayed in gold color, above the other explicit mods).
+    ///
+    /// These cannot be changed by crafting, and do not appear in `explicit`.
+    pub fractured: Vec<Mod>,
+
+    /// Explicit mods of an item
+    /// (those displayed in navy color in the main item pane).
+    ///
+    /// Note that these the mods which are visible in the UI,
+    /// as opposed to *affixes* (prefixes & suffixes) which cannot be reliably
+    /// extrapolated from mods.
+    /// The practical consequence is that there may be more than 6 mods
+    /// which would seemingly contradict the "3 prefixes + 3 suffixes" rule
+    /// (due to the so-called hybrid affixes that result in multiple mods;
+    /// see `Item::hybrid_mods` with the `mods_db` feature).
+    ///
+    /// Mods whose text spans several lines are however kept as a single `Mod`
+    /// (see `Mod::line_count`), even if the API reports their lines separately.
+    pub explicit: Vec<Mod>,
+
+    /// Veiled mods of an item
+    /// (unrevealed affixes that are displayed as "Veiled Prefix" or "Veiled Suffix").
+    pub veiled: Vec<Mod>,
+
+    /// Crafted mods on an item
+    /// (those displayed in light blue color in the main item pane, below explicit mods).
+    pub crafted: Vec<Mod>,
+}
+
+impl GearDetails {
+    /// All mods of the item,
+    /// in the top-down order with respect to the in-game UI.
+    pub fn mods<'m>(&'m self) -> Box<Iterator<Item=&'m Mod> + 'm> {
+        Box::new(
+            self.implicit.iter()
+                .chain(self.enchants.iter())
+                .chain(self.fractured.iter())
+                .chain(self.explicit.iter())
+                .chain(self.veiled.iter())
+                .chain(self.crafted.iter())
+        )
+    }
+
+    /// Explicit mods of the item, including the fractured ones.
+    pub fn explicit_mods<'m>(&'m self) -> Box<Iterator<Item=&'m Mod> + 'm> {
+        Box::new(self.fractured.iter().chain(self.explicit.iter()))
+    }
+
+    /// Whether the item has any master-crafted mods.
+    #[inline]
+    pub fn is_crafted(&self) -> bool {
+        !self.crafted.is_empty()
+    }
+
+    /// Whether the item has any fractured mods.
+    #[inline]
+    pub fn is_fractured(&self) -> bool {
+        !self.fractured.is_empty()
+    }
+
+    /// Whether the item has any veiled mods.
+    #[inline]
+    pub fn is_veiled(&self) -> bool {
+        !self.veiled.is_empty()
+    }
+}
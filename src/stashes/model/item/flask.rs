@@ -0,0 +1,187 @@
+//! Flask specifics: charge & effect modifiers, and the uptime of flask effects.
+
+use regex::Regex;
+
+use util::parse_number;
+use super::FlaskDetails;
+
+
+/// Assumption about how quickly a flask gains charges,
+/// before its own charge recovery modifiers are applied.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChargeGain {
+    /// Flask gains charges by killing monsters.
+    Kills {
+        /// Number of monsters killed per second.
+        per_second: f64,
+        /// Number of flask charges gained for every kill.
+        charges_per_kill: f64,
+    },
+    /// Flask gains given number of charges per second.
+    PerSecond(f64),
+}
+
+impl ChargeGain {
+    /// Number of flask charges gained per second.
+    pub fn charges_per_second(&self) -> f64 {
+        match *self {
+            ChargeGain::Kills{per_second, charges_per_kill} => per_second * charges_per_kill,
+            ChargeGain::PerSecond(charges) => charges,
+        }
+    }
+}
+
+/// Sustainable uptime of a flask effect.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FlaskUptime {
+    /// Fraction (0..=1) of time the flask effect is active.
+    pub uptime: f64,
+    /// Number of times the flask can be used per minute.
+    pub uses_per_minute: f64,
+}
+
+
+impl FlaskDetails {
+    /// Percentage of increased charge recovery of the flask
+    /// (negative if it's reduced).
+    #[inline]
+    pub fn charge_recovery(&self) -> i32 {
+        self.increased("Charge Recovery")
+    }
+
+    /// Percentage of increased duration of the flask effect
+    /// (negative if it's reduced).
+    ///
+    /// Note that `duration` already takes this into account.
+    #[inline]
+    pub fn duration_modifier(&self) -> i32 {
+        self.increased("Duration")
+    }
+
+    /// Percentage of increased effect of the flask
+    /// (negative if it's reduced).
+    #[inline]
+    pub fn effect_modifier(&self) -> i32 {
+        self.increased("effect")
+    }
+
+    /// Compute the uptime of the flask effect that can be sustained
+    /// when the flask gains charges at given rate.
+    ///
+    /// The flask is assumed to be used again as soon as its effect expires,
+    /// provided it has enough charges by then.
+    pub fn uptime(&self, gain: ChargeGain) -> FlaskUptime {
+        let duration = self.duration.as_secs() as f64
+            + self.duration.subsec_nanos() as f64 / 1_000_000_000.0;
+        if self.charges_per_use == 0 {
+            return FlaskUptime{uptime: 1.0, uses_per_minute: 60.0 / duration};
+        }
+        let charges_per_second = gain.charges_per_second()
+            * (100.0 + self.charge_recovery() as f64) / 100.0;
+        let uses_per_second = (charges_per_second / self.charges_per_use as f64)
+            .min(1.0 / duration).max(0.0);
+        FlaskUptime{
+            uptime: (uses_per_second * duration).min(1.0),
+            uses_per_minute: uses_per_second * 60.0,
+        }
+    }
+
+    /// Sum of "#% increased/reduced `stat`" mods of the flask.
+    fn increased(&self, stat: &str) -> i32 {
+        lazy_static! {
+            static ref INCREASED_RE: Regex = Regex::new(
+                r"^(\d+)% (increased|reduced) (.+)$").unwrap();
+        }
+        let total: f64 = self.mods.iter()
+            .filter_map(|m| INCREASED_RE.captures(m.as_str()))
+            .filter(|caps| &caps[3] == stat)
+            .filter_map(|caps| parse_number(&caps[1]).ok()
+                .map(|n| if &caps[2] == "reduced" { -n } else { n }))
+            .sum();
+        total as i32
+    }
+}
+
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use serde_json::from_value;
+    use super::super::Item;
+    use super::ChargeGain;
+
+    #[test]
+    fn modifiers() {
+        let item_spec = json!({
+            "id": "123abc",
+            "name": "",
+            "typeLine": "Quicksilver Flask",
+            "ilvl": 80,
+            "category": "flasks",
+            "frameType": 1,
+            "properties": [
+                {"name": "Lasts %0 Seconds", "values": [["4.00", 0]]},
+                {"name": "Consumes %0 of %1 Charges on use", "values": [["20", 0], ["60", 0]]},
+            ],
+            "utilityMods": ["40% increased Movement Speed"],
+            "explicitMods": [
+                "25% increased effect", "33% reduced Duration",
+                "50% increased Charge Recovery",
+            ],
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        let flask = item.as_flask().unwrap();
+        assert_eq!(50, flask.charge_recovery());
+        assert_eq!(-33, flask.duration_modifier());
+        assert_eq!(25, flask.effect_modifier());
+    }
+
+    #[test]
+    fn uptime() {
+        let item_spec = json!({
+            "id": "123abc",
+            "name": "",
+            "typeLine": "Quicksilver Flask",
+            "ilvl": 80,
+            "category": "flasks",
+            "frameType": 0,
+            "properties": [
+                {"name": "Lasts %0 Seconds", "values": [["4.00", 0]]},
+                {"name": "Consumes %0 of %1 Charges on use", "values": [["20", 0], ["60", 0]]},
+            ],
+            "utilityMods": ["40% increased Movement Speed"],
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        let flask = item.as_flask().unwrap();
+        // 2.5 charges per second suffice for one use every 8 seconds.
+        let uptime = flask.uptime(ChargeGain::Kills{per_second: 2.5, charges_per_kill: 1.0});
+        assert_eq!(0.5, uptime.uptime);
+        assert_eq!(7.5, uptime.uses_per_minute);
+        // Surplus charges don't make the effect last longer than all the time.
+        let uptime = flask.uptime(ChargeGain::PerSecond(100.0));
+        assert_eq!(1.0, uptime.uptime);
+        assert_eq!(15.0, uptime.uses_per_minute);
+    }
+
+    #[test]
+    fn uptime__charge_recovery() {
+        let item_spec = json!({
+            "id": "123abc",
+            "name": "",
+            "typeLine": "Quicksilver Flask",
+            "ilvl": 80,
+            "category": "flasks",
+            "frameType": 1,
+            "properties": [
+                {"name": "Lasts %0 Seconds", "values": [["4.00", 0]]},
+                {"name": "Consumes %0 of %1 Charges on use", "values": [["20", 0], ["60", 0]]},
+            ],
+            "utilityMods": ["40% increased Movement Speed"],
+            "explicitMods": ["50% increased Charge Recovery"],
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        let flask = item.as_flask().unwrap();
+        let uptime = flask.uptime(ChargeGain::PerSecond(2.5));
+        assert_eq!(0.75, uptime.uptime);
+    }
+}
@@ -0,0 +1,129 @@
+//! Item frame type.
+
+use super::{ItemCategory, Rarity};
+
+
+/// Type of the frame that an item is displayed with in the game UI.
+///
+/// For most items, this corresponds to their rarity,
+/// but some item types (like gems or divination cards) have distinct frames.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum FrameType {
+    /// Frame of a normal ("white") item.
+    Normal,
+    /// Frame of a magic ("blue") item.
+    Magic,
+    /// Frame of a rare ("yellow") item.
+    Rare,
+    /// Frame of a unique item.
+    Unique,
+    /// Frame of a skill gem.
+    Gem,
+    /// Frame of a currency item.
+    Currency,
+    /// Frame of a divination card.
+    DivinationCard,
+    /// Frame of a quest item.
+    Quest,
+    /// Frame of a sealed prophecy item.
+    Prophecy,
+    /// Frame of a relic, i.e. a unique item with a special foil appearance.
+    Relic,
+    /// Frame type not (yet) known to this library, with its index from the API.
+    Other(u64),
+}
+
+impl Default for FrameType {
+    fn default() -> Self {
+        FrameType::Normal
+    }
+}
+
+impl FrameType {
+    /// Rarity of items displayed with this frame.
+    ///
+    /// Items that don't have a rarity frame (like gems or currency) are `Normal`,
+    /// whereas relics are `Unique`.
+    pub fn rarity(&self) -> Rarity {
+        match *self {
+            FrameType::Magic => Rarity::Magic,
+            FrameType::Rare => Rarity::Rare,
+            FrameType::Unique | FrameType::Relic => Rarity::Unique,
+            _ => Rarity::Normal,
+        }
+    }
+
+    /// Category of items that are displayed with this frame, if it determines one.
+    pub fn category(&self) -> Option<ItemCategory> {
+        match *self {
+            FrameType::Gem => Some(ItemCategory::Gem),
+            FrameType::Currency => Some(ItemCategory::Currency),
+            FrameType::DivinationCard => Some(ItemCategory::DivinationCard),
+            FrameType::Prophecy => Some(ItemCategory::Prophecy),
+            FrameType::Relic => Some(ItemCategory::Relic),
+            _ => None,
+        }
+    }
+
+    /// Whether the API can report given item category for items with this frame.
+    ///
+    /// This isn't always the same as `category`:
+    /// sealed prophecies are reported as currency, for example,
+    /// and relics are reported under the category of their base item.
+    pub fn admits_category(&self, category: &ItemCategory) -> bool {
+        let is_currency = match *category {
+            ItemCategory::Currency | ItemCategory::Essence | ItemCategory::Fossil |
+            ItemCategory::Resonator | ItemCategory::Net | ItemCategory::Incubator |
+            ItemCategory::Oil | ItemCategory::Fragment | ItemCategory::Scarab => true,
+            _ => false,
+        };
+        let has_rarity = !is_currency && match *category {
+            ItemCategory::Gem | ItemCategory::DivinationCard |
+            ItemCategory::Prophecy | ItemCategory::Relic => false,
+            _ => true,
+        };
+        // Map fragments and scarabs are also displayed with the normal item frame.
+        let is_map_item = match *category {
+            ItemCategory::Fragment | ItemCategory::Scarab => true,
+            _ => false,
+        };
+        match *self {
+            FrameType::Normal => has_rarity || is_map_item,
+            FrameType::Magic | FrameType::Rare | FrameType::Unique => has_rarity,
+            FrameType::Currency => is_currency,
+            FrameType::Prophecy => *category == ItemCategory::Prophecy
+                || *category == ItemCategory::Currency,
+            FrameType::Relic => *category == ItemCategory::Relic || has_rarity,
+            FrameType::Quest | FrameType::Other(_) => true,
+            ref frame => frame.category().as_ref() == Some(category),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::super::{ArmourType, ItemCategory, Rarity};
+    use super::FrameType;
+
+    #[test]
+    fn rarity() {
+        assert_eq!(Rarity::Rare, FrameType::Rare.rarity());
+        assert_eq!(Rarity::Unique, FrameType::Relic.rarity());
+        assert_eq!(Rarity::Normal, FrameType::Gem.rarity());
+    }
+
+    #[test]
+    fn admits_category() {
+        let chest = ItemCategory::Armour(ArmourType::Chest);
+        assert!(FrameType::Rare.admits_category(&chest));
+        assert!(!FrameType::Rare.admits_category(&ItemCategory::Gem));
+        assert!(FrameType::Relic.admits_category(&chest));
+        assert!(FrameType::Prophecy.admits_category(&ItemCategory::Currency));
+        assert!(!FrameType::DivinationCard.admits_category(&ItemCategory::Currency));
+        assert!(FrameType::Currency.admits_category(&ItemCategory::Fossil));
+        assert!(FrameType::Normal.admits_category(&ItemCategory::Fragment));
+        assert!(!FrameType::Rare.admits_category(&ItemCategory::Scarab));
+        assert!(FrameType::Other(42).admits_category(&chest));
+    }
+}
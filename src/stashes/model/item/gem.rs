@@ -0,0 +1,311 @@
+//! Skill gem specifics: tags, variants and experience.
+//!
+//! The experience tables of gems are compiled in by the build script
+//! from `data/gems.json` (see the `update-gems` recipe in the justfile).
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use conv::errors::Unrepresentable;
+
+use super::Experience;
+
+
+lazy_static! {
+    /// Total experience that gems need to reach particular levels, by gem names.
+    ///
+    /// The tables may be incomplete, so the levels are stored explicitly.
+    static ref EXPERIENCE: HashMap<&'static str, &'static [(u32, u64)]> = {
+        let tables: Vec<(&'static str, &'static [(u32, u64)])> = include!(concat!(
+            env!("OUT_DIR"), "/", "model/item/gem/experience.inc.rs"));
+        tables.into_iter().collect()
+    };
+}
+
+/// Gems whose maximum level is much lower than usual.
+const LOW_MAX_LEVEL_GEMS: &[&str] = &[
+    "Empower Support", "Enhance Support", "Enlighten Support",
+];
+
+
+macro_attr! {
+    /// Tag of a skill gem, like Spell or Minion.
+    ///
+    /// Tags are shown in the game UI below the gem name.
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq,
+             IterVariants!(GemTags))]
+    pub enum GemTag {
+        /// Gem of an attack skill.
+        Attack,
+        /// Gem of a spell.
+        Spell,
+        /// Support gem.
+        Support,
+        /// Skill affecting an area.
+        AoE,
+        /// Skill with a duration.
+        Duration,
+        /// Skill firing projectiles.
+        Projectile,
+        /// Skill performing melee attacks.
+        Melee,
+        /// Skill that summons minions.
+        Minion,
+        /// Aura skill.
+        Aura,
+        /// Skill usable with bows.
+        Bow,
+        /// Skill that creates totems.
+        Totem,
+        /// Curse skill.
+        Curse,
+        /// Vaal skill, requiring souls to use.
+        Vaal,
+        /// Skill that throws traps.
+        Trap,
+        /// Skill that places mines.
+        Mine,
+        /// Movement skill.
+        Movement,
+        /// Skill that is triggered rather than used directly.
+        Trigger,
+        /// Skill with chaining projectiles.
+        Chaining,
+        /// Skill that summons a golem.
+        Golem,
+        /// Skill that casts other skills.
+        Cast,
+        /// Herald skill.
+        Herald,
+        /// Channelling skill.
+        Channelling,
+        /// Warcry skill.
+        Warcry,
+        /// Skill dealing physical damage.
+        Physical,
+        /// Skill dealing fire damage.
+        Fire,
+        /// Skill dealing cold damage.
+        Cold,
+        /// Skill dealing lightning damage.
+        Lightning,
+        /// Skill dealing chaos damage.
+        Chaos,
+    }
+}
+
+impl FromStr for GemTag {
+    type Err = Unrepresentable<String>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        GemTag::iter_variants()
+            .find(|t| t.as_str() == s)
+            .ok_or_else(|| Unrepresentable(s.to_owned()))
+    }
+}
+
+impl GemTag {
+    /// Name of the tag as it's displayed in the game UI.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            GemTag::Attack => "Attack",
+            GemTag::Spell => "Spell",
+            GemTag::Support => "Support",
+            GemTag::AoE => "AoE",
+            GemTag::Duration => "Duration",
+            GemTag::Projectile => "Projectile",
+            GemTag::Melee => "Melee",
+            GemTag::Minion => "Minion",
+            GemTag::Aura => "Aura",
+            GemTag::Bow => "Bow",
+            GemTag::Totem => "Totem",
+            GemTag::Curse => "Curse",
+            GemTag::Vaal => "Vaal",
+            GemTag::Trap => "Trap",
+            GemTag::Mine => "Mine",
+            GemTag::Movement => "Movement",
+            GemTag::Trigger => "Trigger",
+            GemTag::Chaining => "Chaining",
+            GemTag::Golem => "Golem",
+            GemTag::Cast => "Cast",
+            GemTag::Herald => "Herald",
+            GemTag::Channelling => "Channelling",
+            GemTag::Warcry => "Warcry",
+            GemTag::Physical => "Physical",
+            GemTag::Fire => "Fire",
+            GemTag::Cold => "Cold",
+            GemTag::Lightning => "Lightning",
+            GemTag::Chaos => "Chaos",
+        }
+    }
+}
+
+
+/// Variant of a skill gem.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum GemVariant {
+    /// Regular gem.
+    Regular,
+    /// Vaal version of a gem, like "Vaal Fireball".
+    Vaal,
+    /// Awakened version of a support gem, like "Awakened Added Fire Damage Support".
+    Awakened,
+}
+
+impl Default for GemVariant {
+    fn default() -> Self {
+        GemVariant::Regular
+    }
+}
+
+impl GemVariant {
+    /// Determine the variant of a gem from its name.
+    pub fn of(gem: &str) -> Self {
+        if gem.starts_with("Vaal ") {
+            GemVariant::Vaal
+        } else if gem.starts_with("Awakened ") {
+            GemVariant::Awakened
+        } else {
+            GemVariant::Regular
+        }
+    }
+}
+
+
+/// Highest level that given gem can reach by gaining experience.
+///
+/// Levels above that can only be obtained through corruption
+/// (hence the "21/20" gems).
+pub fn gem_level_cap(gem: &str) -> u32 {
+    if LOW_MAX_LEVEL_GEMS.contains(&gem) {
+        return 3;
+    }
+    match GemVariant::of(gem) {
+        GemVariant::Awakened => 5,
+        _ => 20,
+    }
+}
+
+/// Total experience that given gem needs in order to reach given level, if known.
+///
+/// Gems start at level 1 with no experience.
+pub fn gem_experience(gem: &str, level: u32) -> Option<u64> {
+    if level <= 1 {
+        return Some(0);
+    }
+    EXPERIENCE.get(gem).and_then(|table| {
+        table.iter().find(|&&(l, _)| l == level).map(|&(_, xp)| xp)
+    })
+}
+
+
+impl Experience {
+    /// Progress of a gem of given name & level towards its next level,
+    /// as a 0..=1 fraction.
+    ///
+    /// Unlike `fraction`, this only counts the experience
+    /// gained since the gem has reached its current level.
+    /// Gems at (or past) their level cap have no further levels to progress towards,
+    /// so their progress is always complete.
+    ///
+    /// Returns `None` if the experience table of the gem is not known.
+    pub fn level_progress(&self, gem: &str, level: u32) -> Option<f64> {
+        if level >= gem_level_cap(gem) {
+            return Some(1.0);
+        }
+        let start = gem_experience(gem, level)?;
+        if start >= self.total() {
+            return None;
+        }
+        let gained = self.current().saturating_sub(start);
+        Some(gained as f64 / (self.total() - start) as f64)
+    }
+
+    /// Level that a gem of given name & level will reach
+    /// after gaining given amount of additional experience.
+    ///
+    /// Gems never level past their level cap by gaining experience,
+    /// so the level of gems that are already there (or past it) stays the same.
+    ///
+    /// Returns `None` if the experience table of the gem doesn't cover
+    /// all the levels in question.
+    pub fn level_after(&self, gem: &str, level: u32, gained: u64) -> Option<u32> {
+        let cap = gem_level_cap(gem);
+        if level >= cap {
+            return Some(level);
+        }
+        let xp = self.current() + gained;
+        if xp < self.total() {
+            return Some(level);
+        }
+        let mut level = level + 1;
+        while level < cap {
+            if xp < gem_experience(gem, level + 1)? {
+                break;
+            }
+            level += 1;
+        }
+        Some(level)
+    }
+}
+
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use std::str::FromStr;
+    use super::super::Experience;
+    use super::{GemTag, GemVariant, gem_experience, gem_level_cap};
+
+    #[test]
+    fn tag_strings() {
+        for tag in GemTag::iter_variants() {
+            assert_eq!(tag, GemTag::from_str(tag.as_str()).unwrap());
+        }
+        assert!(GemTag::from_str("Level").is_err());
+    }
+
+    #[test]
+    fn variants() {
+        assert_eq!(GemVariant::Regular, GemVariant::of("Fireball"));
+        assert_eq!(GemVariant::Vaal, GemVariant::of("Vaal Fireball"));
+        assert_eq!(GemVariant::Awakened, GemVariant::of("Awakened Melee Splash Support"));
+        assert_eq!(20, gem_level_cap("Vaal Fireball"));
+        assert_eq!(5, gem_level_cap("Awakened Melee Splash Support"));
+        assert_eq!(3, gem_level_cap("Empower Support"));
+    }
+
+    #[test]
+    fn experience_table() {
+        assert_eq!(Some(0), gem_experience("Frost Blades", 1));
+        assert_eq!(Some(308), gem_experience("Frost Blades", 3));
+        assert_eq!(None, gem_experience("Frost Blades", 19));
+        assert_eq!(None, gem_experience("Not A Gem", 2));
+    }
+
+    #[test]
+    fn level_after() {
+        let xp = Experience::new(200, 308);
+        assert_eq!(Some(2), xp.level_after("Frost Blades", 2, 100));
+        assert_eq!(None, xp.level_after("Frost Blades", 2, 1000));
+        assert_eq!(Some(20), Experience::full(1).level_after("Vaal Fireball", 19, 0));
+    }
+
+    #[test]
+    fn level_after__cap() {
+        let xp = Experience::new(100, 100);
+        assert_eq!(Some(20), xp.level_after("Frost Blades", 20, 1_000_000_000));
+        assert_eq!(Some(21), xp.level_after("Frost Blades", 21, 1_000_000_000));
+        assert_eq!(Some(3), xp.level_after("Empower Support", 2, 1_000_000_000));
+        assert_eq!(Some(5), xp.level_after("Awakened Melee Splash Support", 5, 1));
+    }
+
+    #[test]
+    fn level_progress() {
+        assert_eq!(Some(0.5), Experience::new(35, 70).level_progress("Frost Blades", 1));
+        assert_eq!(Some(0.5), Experience::new(189, 308).level_progress("Frost Blades", 2));
+        assert_eq!(None, Experience::new(200, 308).level_progress("Not A Gem", 2));
+        assert_eq!(Some(1.0), Experience::new(0, 1).level_progress("Not A Gem", 20));
+        assert_eq!(Some(1.0), Experience::new(0, 1).level_progress("Empower Support", 3));
+    }
+}
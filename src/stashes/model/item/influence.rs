@@ -1,8 +1,15 @@
-//! Influence that War for the Atlas has over the item.
+//! Influence that the Atlas factions have over the item.
+
+use std::str::FromStr;
+
+use conv::errors::Unrepresentable;
 
 
 macro_attr! {
-    /// War for the Atlas influence on the item.
+    /// Atlas influence on the item.
+    ///
+    /// An item may have more than one influence
+    /// (e.g. after using Awakener's Orb).
     #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq,
              IterVariants!(Influences))]
     pub enum Influence {
@@ -12,5 +19,68 @@ macro_attr! {
         /// This item base has been influenced by the Shaper.
         /// This is colloquially referred to as a "shaped item".
         Shaper,
+        /// The item base has been influenced by Sirus' conqueror, Al-Hezmin, the Hunter.
+        Hunter,
+        /// The item base has been influenced by Sirus' conqueror, Baran, the Crusader.
+        Crusader,
+        /// The item base has been influenced by Sirus' conqueror, Drox, the Warlord.
+        Warlord,
+        /// The item base has been influenced by Sirus' conqueror, Veritania, the Redeemer.
+        Redeemer,
+    }
+}
+
+impl Influence {
+    /// Whether this is the influence of one of the Conquerors of the Atlas.
+    #[inline]
+    pub fn is_conqueror(&self) -> bool {
+        match *self {
+            Influence::Elder | Influence::Shaper => false,
+            _ => true,
+        }
+    }
+
+    /// Name of the influence, as used by the API.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Influence::Elder => "elder",
+            Influence::Shaper => "shaper",
+            Influence::Hunter => "hunter",
+            Influence::Crusader => "crusader",
+            Influence::Warlord => "warlord",
+            Influence::Redeemer => "redeemer",
+        }
+    }
+}
+
+impl FromStr for Influence {
+    type Err = Unrepresentable<String>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Influence::iter_variants()
+            .find(|i| i.as_str() == s)
+            .ok_or_else(|| Unrepresentable(s.to_owned()))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use super::Influence;
+
+    #[test]
+    fn strings() {
+        for influence in Influence::iter_variants() {
+            assert_eq!(influence, Influence::from_str(influence.as_str()).unwrap());
+        }
+        assert!(Influence::from_str("Elder").is_err());
+    }
+
+    #[test]
+    fn conquerors() {
+        assert!(!Influence::Shaper.is_conqueror());
+        assert!(Influence::Warlord.is_conqueror());
+        assert_eq!(4, Influence::iter_variants().filter(|i| i.is_conqueror()).count());
     }
 }
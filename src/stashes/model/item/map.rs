@@ -0,0 +1,525 @@
+//! Map specifics: bases, variants, series and dangerous mods.
+
+#[cfg(feature = "mods_db")]
+use std::collections::HashSet;
+#[cfg(feature = "mods_db")]
+use std::iter::FromIterator;
+
+use super::{base_info, split_magic_name, Item, ItemCategory, Rarity};
+#[cfg(feature = "mods_db")]
+use super::{MapDetails, Mod, ModType};
+
+
+/// Variant of a map.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MapVariant {
+    /// Regular map.
+    Regular,
+    /// Shaped map, like "Shaped Arcade Map".
+    Shaped,
+    /// Map influenced by the Elder.
+    Elder,
+    /// Blighted map, like "Blighted Arcade Map".
+    Blighted,
+}
+
+/// Series of maps, i.e. the version of the Atlas that a map comes from.
+///
+/// Maps keep their icons when the Atlas changes,
+/// so that the series can be told apart by the icon.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MapSeries {
+    /// War for the Atlas maps.
+    WarForTheAtlas,
+    /// Betrayal maps.
+    Betrayal,
+    /// Synthesis maps.
+    Synthesis,
+    /// Legion maps.
+    Legion,
+    /// Blight maps.
+    Blight,
+    /// Metamorph maps.
+    Metamorph,
+}
+
+impl MapSeries {
+    /// Determine the map series from the `mn` parameter of the map's icon URL.
+    fn from_icon(icon: &str) -> Option<Self> {
+        let query = icon.splitn(2, '?').nth(1)?;
+        let mn = query.split('&').filter_map(|p| {
+            let mut kv = p.splitn(2, '=');
+            match (kv.next(), kv.next()) {
+                (Some("mn"), Some(v)) => Some(v),
+                _ => None,
+            }
+        }).next()?;
+        match mn {
+            "1" => Some(MapSeries::WarForTheAtlas),
+            "2" => Some(MapSeries::Betrayal),
+            "3" => Some(MapSeries::Synthesis),
+            "4" => Some(MapSeries::Legion),
+            "5" => Some(MapSeries::Blight),
+            "6" => Some(MapSeries::Metamorph),
+            _ => None,
+        }
+    }
+}
+
+
+impl Item {
+    /// Name of the map base, as listed in `data/maps.json`
+    /// (e.g. "Academy Map" or "Shaped Arcade Map"), if this is a known map.
+    ///
+    /// This removes the "Superior" prefix of quality maps
+    /// and the affixes in the names of magic maps.
+    /// Blighted maps aren't listed separately, so they have the base of the regular map
+    /// (e.g. "Arcade Map" for "Blighted Arcade Map").
+    pub fn map_base(&self) -> Option<&'static str> {
+        let base = self.base.trim_start_matches("Superior ").replacen("Blighted ", "", 1);
+        let base = split_magic_name(&base).map(|(_, b, _)| b).unwrap_or(&base);
+        base_info(base)
+            .filter(|info| info.category() == ItemCategory::Map)
+            .map(|info| info.name())
+    }
+
+    /// Variant of the map, if this is a map item.
+    pub fn map_variant(&self) -> Option<MapVariant> {
+        self.as_map()?;
+        let base = self.map_base().unwrap_or(&self.base);
+        // The base of magic items doesn't include the "Blighted" part of their name.
+        let is_blighted = self.base.contains("Blighted ") || (self.rarity == Rarity::Magic
+            && self.name.as_ref().map(|n| n.contains("Blighted ")).unwrap_or(false));
+        Some(if base.starts_with("Shaped ") {
+            MapVariant::Shaped
+        } else if is_blighted {
+            MapVariant::Blighted
+        } else if self.is_elder() {
+            MapVariant::Elder
+        } else {
+            MapVariant::Regular
+        })
+    }
+
+    /// Series of the map, if this is a map item and the series is known.
+    pub fn map_series(&self) -> Option<MapSeries> {
+        self.as_map()?;
+        self.extra.get("icon").and_then(|i| i.as_str()).and_then(MapSeries::from_icon)
+    }
+}
+
+
+macro_attr! {
+    /// Notable map mod which makes the map harder (or impossible) to run for some builds.
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq,
+             IterVariants!(MapMods))]
+    pub enum MapMod {
+        /// "Monsters reflect #% of Physical Damage"
+        PhysicalReflect,
+        /// "Monsters reflect #% of Elemental Damage"
+        ElementalReflect,
+        /// "Monsters reflect Curses"
+        CurseReflect,
+        /// "Players cannot Regenerate Life, Mana or Energy Shield"
+        /// or "Players have no Life or Mana Regeneration"
+        NoRegeneration,
+        /// "Players have #% less Recovery Rate of Life and Energy Shield"
+        LessRecovery,
+        /// "#% Monster Life Leech Resistance" or "#% Monster Mana Leech Resistance"
+        LeechResistance,
+        /// "Players have Elemental Equilibrium"
+        ElementalEquilibrium,
+        /// "Players have Blood Magic"
+        BloodMagic,
+        /// "Players have Point Blank"
+        PointBlank,
+        /// "Players are Cursed with Temporal Chains"
+        TemporalChains,
+        /// "Players are Cursed with Vulnerability"
+        Vulnerability,
+        /// "Players are Cursed with Elemental Weakness"
+        ElementalWeakness,
+        /// "Players are Cursed with Enfeeble"
+        Enfeeble,
+        /// "Players are Cursed with Frostbite"
+        Frostbite,
+        /// "Players are Cursed with Flammability"
+        Flammability,
+        /// "Players are Cursed with Conductivity"
+        Conductivity,
+        /// "Players are Cursed with Projectile Weakness"
+        ProjectileWeakness,
+        /// "Players are Cursed with Warlord's Mark"
+        WarlordsMark,
+        /// "Monsters are Hexproof"
+        Hexproof,
+        /// "Monsters have #% chance to Avoid Elemental Ailments"
+        /// or "Monsters have #% chance to Avoid Ailments"
+        AvoidAilments,
+        /// "Players take # Chaos Damage per second"
+        ChaosDegeneration,
+        /// "Area has patches of burning ground"
+        BurningGround,
+        /// "Area has patches of chilled ground"
+        ChilledGround,
+        /// "Area has patches of shocking ground"
+        ShockingGround,
+        /// "Area has patches of desecrated ground"
+        DesecratedGround,
+    }
+}
+
+/// Category of danger that a `MapMod` poses.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MapDanger {
+    /// Damage that players deal is reflected back at them.
+    Reflect,
+    /// Players' life, mana or energy shield recovery is hampered.
+    Recovery,
+    /// Players are cursed.
+    Curse,
+    /// Players' passives or skills are changed in a way that some builds can't deal with.
+    Restriction,
+    /// Monsters are immune or resistant to some debuffs.
+    MonsterDefence,
+    /// Players take damage over time from the area.
+    Degeneration,
+}
+
+impl MapMod {
+    /// Classify given mod of a map item, if it's a notable one.
+    ///
+    /// The mod has to be resolved against the mod database.
+    #[cfg(feature = "mods_db")]
+    pub fn of(mod_: &Mod) -> Option<Self> {
+        let id = mod_.info()?.id();
+        if id.mod_type() != ModType::Explicit {
+            return None;
+        }
+        let number = id.mod_number()?;
+        MAP_MOD_STATS.iter().find(|&&(n, _)| n == number).map(|&(_, mm)| mm)
+    }
+
+    /// Category of danger that this map mod poses.
+    pub fn danger(&self) -> MapDanger {
+        match *self {
+            MapMod::PhysicalReflect |
+            MapMod::ElementalReflect |
+            MapMod::CurseReflect => MapDanger::Reflect,
+            MapMod::NoRegeneration |
+            MapMod::LessRecovery |
+            MapMod::LeechResistance => MapDanger::Recovery,
+            MapMod::ElementalEquilibrium |
+            MapMod::BloodMagic |
+            MapMod::PointBlank => MapDanger::Restriction,
+            MapMod::TemporalChains |
+            MapMod::Vulnerability |
+            MapMod::ElementalWeakness |
+            MapMod::Enfeeble |
+            MapMod::Frostbite |
+            MapMod::Flammability |
+            MapMod::Conductivity |
+            MapMod::ProjectileWeakness |
+            MapMod::WarlordsMark => MapDanger::Curse,
+            MapMod::Hexproof |
+            MapMod::AvoidAilments => MapDanger::MonsterDefence,
+            MapMod::ChaosDegeneration |
+            MapMod::BurningGround |
+            MapMod::ChilledGround |
+            MapMod::ShockingGround |
+            MapMod::DesecratedGround => MapDanger::Degeneration,
+        }
+    }
+}
+
+/// Numbers of the explicit mod stats that correspond to `MapMod`s.
+#[cfg(feature = "mods_db")]
+const MAP_MOD_STATS: &[(u64, MapMod)] = &[
+    (3464419871, MapMod::PhysicalReflect),
+    (2764017512, MapMod::ElementalReflect),
+    (1341845920, MapMod::CurseReflect),
+    (1910157106, MapMod::NoRegeneration),
+    (1890969167, MapMod::NoRegeneration),
+    (4181072906, MapMod::LessRecovery),
+    (526251910, MapMod::LeechResistance),
+    (918170065, MapMod::LeechResistance),
+    (1714180526, MapMod::ElementalEquilibrium),
+    (86122490, MapMod::BloodMagic),
+    (2835888248, MapMod::PointBlank),
+    (2326202293, MapMod::TemporalChains),
+    (1366534040, MapMod::Vulnerability),
+    (558910024, MapMod::ElementalWeakness),
+    (4103440490, MapMod::Enfeeble),
+    (3442976749, MapMod::Frostbite),
+    (989228672, MapMod::Flammability),
+    (376158712, MapMod::Conductivity),
+    (2116087952, MapMod::ProjectileWeakness),
+    (1877984956, MapMod::WarlordsMark),
+    (4154059009, MapMod::Hexproof),
+    (322206271, MapMod::AvoidAilments),
+    (376585490, MapMod::AvoidAilments),
+    (3031766225, MapMod::ChaosDegeneration),
+    (3665534869, MapMod::BurningGround),
+    (389725673, MapMod::ChilledGround),
+    (3477720557, MapMod::ShockingGround),
+    (3577222856, MapMod::DesecratedGround),
+];
+
+#[cfg(feature = "mods_db")]
+impl MapDetails {
+    /// Notable mods of the map, in the order they appear on the item.
+    pub fn notable_mods(&self) -> Vec<MapMod> {
+        self.mods.iter().filter_map(MapMod::of).collect()
+    }
+}
+
+
+/// Filter of maps which a particular build cannot (or doesn't want to) run.
+///
+/// For example, an elemental spellcaster would typically exclude maps
+/// with `MapMod::ElementalReflect` and `MapMod::Hexproof`.
+#[cfg(feature = "mods_db")]
+#[derive(Clone, Debug, Default)]
+pub struct MapFilter {
+    /// Map mods which make the map unrunnable.
+    unrunnable: HashSet<MapMod>,
+}
+
+#[cfg(feature = "mods_db")]
+impl MapFilter {
+    /// Create a `MapFilter` which excludes maps with any of given mods.
+    #[inline]
+    pub fn new<I: IntoIterator<Item=MapMod>>(unrunnable: I) -> Self {
+        MapFilter{unrunnable: unrunnable.into_iter().collect()}
+    }
+
+    /// Create a `MapFilter` which excludes maps with any mods of given danger categories.
+    pub fn with_dangers<I: IntoIterator<Item=MapDanger>>(dangers: I) -> Self {
+        let dangers: HashSet<_> = dangers.into_iter().collect();
+        MapFilter::new(MapMod::iter_variants().filter(|mm| dangers.contains(&mm.danger())))
+    }
+
+    /// Whether a map with given details can be run.
+    #[inline]
+    pub fn is_runnable(&self, map: &MapDetails) -> bool {
+        self.unrunnable_mods(map).is_empty()
+    }
+
+    /// Mods of given map which make it unrunnable.
+    pub fn unrunnable_mods<'m>(&self, map: &'m MapDetails) -> Vec<&'m Mod> {
+        map.mods.iter()
+            .filter(|m| MapMod::of(m).map(|mm| self.unrunnable.contains(&mm)).unwrap_or(false))
+            .collect()
+    }
+}
+
+#[cfg(feature = "mods_db")]
+impl FromIterator<MapMod> for MapFilter {
+    fn from_iter<T: IntoIterator<Item=MapMod>>(iter: T) -> Self {
+        MapFilter::new(iter)
+    }
+}
+
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use serde_json::from_value;
+    use super::super::Item;
+    use super::{MapSeries, MapVariant};
+    use super::{MapDanger, MapMod};
+    #[cfg(feature = "mods_db")]
+    use super::MapFilter;
+
+    #[test]
+    fn map_base__quality() {
+        let item_spec = json!({
+            "id": "0123456789abcdef",
+            "name": "", "typeLine": "Superior Arcade Map",
+            "ilvl": 75, "frameType": 0, "identified": true,
+            "category": "maps",
+            "properties": [{"name": "Map Tier", "values": [["5", 0]]}],
+            "icon": "http://web.poecdn.com/image/Art/2DItems/Maps/Atlas2Maps/New/Arcade.png?scale=1&w=1&h=1&mn=1&mt=5",
+            "league": "Standard", "verified": false, "w": 1, "h": 1
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        assert_eq!(Some("Arcade Map"), item.map_base());
+    }
+
+    #[test]
+    fn map_base__shaped() {
+        let item_spec = json!({
+            "id": "0123456789abcdef",
+            "name": "", "typeLine": "Shaped Arcade Map",
+            "ilvl": 75, "frameType": 0, "identified": true,
+            "category": "maps",
+            "properties": [{"name": "Map Tier", "values": [["5", 0]]}],
+            "icon": "http://web.poecdn.com/image/Art/2DItems/Maps/Atlas2Maps/New/Arcade.png?scale=1&w=1&h=1&mn=1&mt=5",
+            "league": "Standard", "verified": false, "w": 1, "h": 1
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        assert_eq!(Some("Shaped Arcade Map"), item.map_base());
+    }
+
+    #[test]
+    fn map_base__magic() {
+        let item_spec = json!({
+            "id": "0123456789abcdef",
+            "name": "", "typeLine": "Hexproof Arcade Map of Giants",
+            "ilvl": 75, "frameType": 1, "identified": true,
+            "category": "maps",
+            "properties": [{"name": "Map Tier", "values": [["5", 0]]}],
+            "icon": "http://web.poecdn.com/image/Art/2DItems/Maps/Atlas2Maps/New/Arcade.png?scale=1&w=1&h=1&mn=1&mt=5",
+            "league": "Standard", "verified": false, "w": 1, "h": 1
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        assert_eq!(Some("Arcade Map"), item.map_base());
+    }
+
+    #[test]
+    fn map_base__blighted() {
+        let item_spec = json!({
+            "id": "0123456789abcdef",
+            "name": "", "typeLine": "Blighted Arcade Map",
+            "ilvl": 75, "frameType": 0, "identified": true,
+            "category": "maps",
+            "properties": [{"name": "Map Tier", "values": [["5", 0]]}],
+            "icon": "http://web.poecdn.com/image/Art/2DItems/Maps/Atlas2Maps/New/Arcade.png?scale=1&w=1&h=1&mn=5&mt=5",
+            "league": "Standard", "verified": false, "w": 1, "h": 1
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        assert_eq!(Some("Arcade Map"), item.map_base());
+    }
+
+    #[test]
+    fn map_base__magic_blighted() {
+        let item_spec = json!({
+            "id": "0123456789abcdef",
+            "name": "", "typeLine": "Hexproof Blighted Arcade Map of Giants",
+            "ilvl": 75, "frameType": 1, "identified": true,
+            "category": "maps",
+            "properties": [{"name": "Map Tier", "values": [["5", 0]]}],
+            "icon": "http://web.poecdn.com/image/Art/2DItems/Maps/Atlas2Maps/New/Arcade.png?scale=1&w=1&h=1&mn=5&mt=5",
+            "league": "Standard", "verified": false, "w": 1, "h": 1
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        assert_eq!(Some("Arcade Map"), item.map_base());
+        assert_eq!(Some(MapVariant::Blighted), item.map_variant());
+    }
+
+    #[test]
+    fn map_variant__regular() {
+        let item_spec = json!({
+            "id": "0123456789abcdef",
+            "name": "", "typeLine": "Arcade Map",
+            "ilvl": 75, "frameType": 0, "identified": true,
+            "category": "maps",
+            "properties": [{"name": "Map Tier", "values": [["5", 0]]}],
+            "icon": "http://web.poecdn.com/image/Art/2DItems/Maps/Atlas2Maps/New/Arcade.png?scale=1&w=1&h=1&mn=1&mt=5",
+            "league": "Standard", "verified": false, "w": 1, "h": 1
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        assert_eq!(Some(MapVariant::Regular), item.map_variant());
+    }
+
+    #[test]
+    fn map_variant__shaped() {
+        let item_spec = json!({
+            "id": "0123456789abcdef",
+            "name": "", "typeLine": "Shaped Arcade Map",
+            "ilvl": 75, "frameType": 0, "identified": true,
+            "category": "maps",
+            "properties": [{"name": "Map Tier", "values": [["5", 0]]}],
+            "icon": "http://web.poecdn.com/image/Art/2DItems/Maps/Atlas2Maps/New/Arcade.png?scale=1&w=1&h=1&mn=1&mt=5",
+            "league": "Standard", "verified": false, "w": 1, "h": 1
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        assert_eq!(Some(MapVariant::Shaped), item.map_variant());
+    }
+
+    #[test]
+    fn map_variant__blighted() {
+        let item_spec = json!({
+            "id": "0123456789abcdef",
+            "name": "", "typeLine": "Blighted Arcade Map",
+            "ilvl": 75, "frameType": 0, "identified": true,
+            "category": "maps",
+            "properties": [{"name": "Map Tier", "values": [["5", 0]]}],
+            "icon": "http://web.poecdn.com/image/Art/2DItems/Maps/Atlas2Maps/New/Arcade.png?scale=1&w=1&h=1&mn=5&mt=5",
+            "league": "Standard", "verified": false, "w": 1, "h": 1
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        assert_eq!(Some(MapVariant::Blighted), item.map_variant());
+    }
+
+    #[test]
+    fn map_series() {
+        let item_spec = json!({
+            "id": "0123456789abcdef",
+            "name": "", "typeLine": "Arcade Map",
+            "ilvl": 75, "frameType": 0, "identified": true,
+            "category": "maps",
+            "properties": [{"name": "Map Tier", "values": [["5", 0]]}],
+            "icon": "http://web.poecdn.com/image/Art/2DItems/Maps/Atlas2Maps/New/Arcade.png?scale=1&w=1&h=1&mn=1&mt=5",
+            "league": "Standard", "verified": false, "w": 1, "h": 1
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        assert_eq!(Some(MapSeries::WarForTheAtlas), item.map_series());
+        assert_eq!(None, MapSeries::from_icon("http://web.poecdn.com/Map56.png?scale=1"));
+    }
+
+    #[test]
+    fn dangers() {
+        assert_eq!(MapDanger::Reflect, MapMod::CurseReflect.danger());
+        assert_eq!(MapDanger::Curse, MapMod::TemporalChains.danger());
+        assert_eq!(MapDanger::Recovery, MapMod::NoRegeneration.danger());
+    }
+
+    #[cfg(feature = "mods_db")]
+    #[test]
+    fn notable_mods() {
+        let item_spec = json!({
+            "id": "0123456789abcdef",
+            "name": "Grim Trap", "typeLine": "Arcade Map",
+            "ilvl": 75, "frameType": 2, "identified": true,
+            "category": "maps",
+            "properties": [{"name": "Map Tier", "values": [["5", 0]]}],
+            "explicitMods": [
+                "Monsters reflect 18% of Elemental Damage",
+                "Players are Cursed with Temporal Chains",
+                "30% more Magic Monsters"
+            ],
+            "icon": "http://web.poecdn.com/image/Art/2DItems/Maps/Atlas2Maps/New/Arcade.png?scale=1&w=1&h=1&mn=1&mt=5",
+            "league": "Standard", "verified": false, "w": 1, "h": 1
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        let mods = item.as_map().unwrap().notable_mods();
+        assert_eq!(vec![MapMod::ElementalReflect, MapMod::TemporalChains], mods);
+        assert_eq!(MapDanger::Reflect, mods[0].danger());
+    }
+
+    #[cfg(feature = "mods_db")]
+    #[test]
+    fn map_filter() {
+        let item_spec = json!({
+            "id": "0123456789abcdef",
+            "name": "Grim Trap", "typeLine": "Arcade Map",
+            "ilvl": 75, "frameType": 2, "identified": true,
+            "category": "maps",
+            "properties": [{"name": "Map Tier", "values": [["5", 0]]}],
+            "explicitMods": [
+                "Monsters reflect 18% of Elemental Damage",
+                "30% more Magic Monsters"
+            ],
+            "icon": "http://web.poecdn.com/image/Art/2DItems/Maps/Atlas2Maps/New/Arcade.png?scale=1&w=1&h=1&mn=1&mt=5",
+            "league": "Standard", "verified": false, "w": 1, "h": 1
+        });
+        let item = from_value::<Item>(item_spec).unwrap();
+        let map = item.as_map().unwrap();
+        let filter = MapFilter::new(vec![MapMod::PhysicalReflect]);
+        assert!(filter.is_runnable(map));
+        let filter = MapFilter::with_dangers(vec![MapDanger::Reflect]);
+        assert!(!filter.is_runnable(map));
+        assert_eq!(1, filter.unrunnable_mods(map).len());
+    }
+}
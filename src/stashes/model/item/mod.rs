@@ -1,32 +1,65 @@
 //! Items that can be traded.
 
+#[cfg(feature = "mods_db")]
+mod affixes;
+mod base;
 mod category;
+mod crafting;
 mod details;
 mod experience;
+mod flask;
+mod frame;
+mod gem;
 mod influence;
+mod map;
 mod mods;
 mod properties;
 mod quality;
 mod rarity;
 mod sockets;
+mod stats;
 
+#[cfg(feature = "mods_db")]
+pub use self::affixes::AffixAssignment;
+pub use self::base::{
+    base_info, BaseImplicit, BaseInfo, BaseWeaponStats, is_base_type, split_magic_name,
+};
 pub use self::category::*;
-pub use self::details::ItemDetails;
+pub use self::crafting::{ColorChances, CraftingMethod, CraftingOption};
+pub use self::details::{FlaskDetails, GearDetails, GemDetails, ItemDetails, MapDetails};
 pub use self::experience::Experience;
+pub use self::flask::{ChargeGain, FlaskUptime};
+pub use self::frame::FrameType;
+pub use self::gem::{gem_experience, gem_level_cap, GemTag, GemVariant};
 pub use self::influence::Influence;
-pub use self::mods::{Mod, ModType};
+pub use self::map::{MapDanger, MapMod, MapSeries, MapVariant};
+#[cfg(feature = "mods_db")]
+pub use self::map::MapFilter;
+pub use self::mods::{Mod, ModIdError, ModType};
+#[cfg(feature = "mods_db")]
+pub use self::mods::{Database as ModDatabase, DatabaseLoadError as ModDatabaseLoadError};
+#[cfg(feature = "mods_db")]
+pub use self::mods::{Affix, AffixStat, AffixType, ModTier};
+#[cfg(feature = "mods_db")]
+pub use self::mods::database::{
+    current as mods_database,
+    initialize as initialize_mods_database,
+    replace as replace_mods_database,
+};
 pub use self::properties::Properties;
 pub use self::quality::Quality;
 pub use self::rarity::Rarity;
 pub use self::sockets::*;
+pub use self::stats::{Defences, MAX_QUALITY, WeaponDps};
 
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::iter;
 
 use serde_json::Value as Json;
 
 use ::common::util::Quasi;
+use super::currency::Currency;
 
 
 
@@ -56,6 +89,11 @@ pub struct Item {
     ///
     /// For items other than gear, this will be just `Normal`.
     pub rarity: Rarity,
+    /// Type of the frame the item is displayed with.
+    ///
+    /// This is mostly redundant with `rarity` and `category`,
+    /// but it also distinguishes e.g. quest items and relics.
+    pub frame_type: FrameType,
     /// Item quality.
     pub quality: Quality,
     /// Innate item properties.
@@ -64,7 +102,9 @@ pub struct Item {
     /// like armor/evasion/energy shield values and weapon damage range.
     /// Properties may have values (like damage ranges)
     /// or not (like gem "tags", e.g. "Spell", "Support", etc).
-    pub properties: Properties,  // TODO: parse "X-Y" ranges
+    ///
+    /// Some of them are interpreted by methods like `weapon_dps` or `defences`.
+    pub properties: Properties,
     /// Item details.
     ///
     /// These are specific to the particular kind of item.
@@ -74,7 +114,9 @@ pub struct Item {
     /// this will be `None`.
     pub details: Option<ItemDetails>,
     /// Sockets an item has, if any.
-    pub sockets: ItemSockets,  // TODO: socketedItems
+    ///
+    /// This also includes the items (gems and abyss jewels) socketed therein.
+    pub sockets: ItemSockets,
     /// Extra item attributes that do not fit into any other part of the schema.
     ///
     /// This may include specific attributes of certain discontinued item types,
@@ -85,25 +127,48 @@ pub struct Item {
     pub requirements: HashMap<Requirement, u32>,
     /// Whether the item is corrupted.
     pub corrupted: bool,
-    /// Whether the item has been influenced by the War of the Atlas.
-    pub influence: Option<Influence>,
+    /// Atlas influences that the item has (if any).
+    pub influences: HashSet<Influence>,
     /// Whether the item has been duplicated (mirrored).
     pub duplicated: bool,
+    /// Whether the item is fractured (has some of its mods locked in place).
+    pub fractured: bool,
+    /// Whether the item is synthesised (has implicit mods from the Synthesis league).
+    pub synthesised: bool,
     /// Flavor text associated with the item.
     pub flavour_text: Option<String>,
+    /// Number of items in the stack, for stackable items (like currency or divination cards).
+    ///
+    /// This is 1 for items which aren't stackable.
+    pub stack_size: u32,
+    /// Maximum number of items in a single stack,
+    /// or `None` if the item isn't stackable.
+    pub max_stack_size: Option<u32>,
 }
 
 impl Item {
+    /// Whether this item has a base influenced by given Atlas faction.
+    #[inline]
+    pub fn has_influence(&self, influence: Influence) -> bool {
+        self.influences.contains(&influence)
+    }
+
     /// Whether this item has an Elder-influenced base.
     #[inline]
     pub fn is_elder(&self) -> bool {
-        self.influence == Some(Influence::Elder)
+        self.has_influence(Influence::Elder)
     }
 
     /// Whether this item has a Shaper-influenced base.
     #[inline]
     pub fn is_shaped(&self) -> bool {
-        self.influence == Some(Influence::Shaper)
+        self.has_influence(Influence::Shaper)
+    }
+
+    /// Whether this item has a base influenced by any of the Conquerors of the Atlas.
+    #[inline]
+    pub fn is_conqueror_influenced(&self) -> bool {
+        self.influences.iter().any(|i| i.is_conqueror())
     }
 
     /// Whether this is a unique item.
@@ -119,11 +184,105 @@ impl Item {
         self.details.as_ref().map(|d| d.is_identified()).unwrap_or(true)
     }
 
+    /// Number of items in the stack.
+    ///
+    /// This is 1 for items which aren't stackable.
+    #[inline]
+    pub fn stack_size(&self) -> u32 {
+        self.stack_size
+    }
+
+    /// Maximum number of items in a single stack,
+    /// or `None` if the item isn't stackable.
+    #[inline]
+    pub fn max_stack_size(&self) -> Option<u32> {
+        self.max_stack_size
+    }
+
+    /// The currency that this item is, if it's a currency item (like Chaos Orb).
+    #[inline]
+    pub fn as_currency(&self) -> Option<Currency> {
+        Currency::from_name(&self.base)
+    }
+
+    /// Details of the item if it's a map.
+    #[inline]
+    pub fn as_map(&self) -> Option<&MapDetails> {
+        match self.details {
+            Some(ItemDetails::Map(ref map)) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// Details of the item if it's a skill gem.
+    #[inline]
+    pub fn as_gem(&self) -> Option<&GemDetails> {
+        match self.details {
+            Some(ItemDetails::Gem(ref gem)) => Some(gem),
+            _ => None,
+        }
+    }
+
+    /// Details of the item if it's a flask.
+    #[inline]
+    pub fn as_flask(&self) -> Option<&FlaskDetails> {
+        match self.details {
+            Some(ItemDetails::Flask(ref flask)) => Some(flask),
+            _ => None,
+        }
+    }
+
+    /// Details of the item if it's an (identified) item that goes in a gear slot.
+    #[inline]
+    pub fn as_gear(&self) -> Option<&GearDetails> {
+        match self.details {
+            Some(ItemDetails::Gear(ref gear)) => Some(gear),
+            _ => None,
+        }
+    }
+
+    /// Name of the prefix of a magic item,
+    /// e.g. "Virile" for "Virile Vaal Regalia of the Titan".
+    #[inline]
+    pub fn name_prefix(&self) -> Option<&str> {
+        self.magic_name_affixes().and_then(|(prefix, _)| prefix)
+    }
+
+    /// Name of the suffix of a magic item,
+    /// e.g. "of the Titan" for "Virile Vaal Regalia of the Titan".
+    #[inline]
+    pub fn name_suffix(&self) -> Option<&str> {
+        self.magic_name_affixes().and_then(|(_, suffix)| suffix)
+    }
+
+    /// Find the names of prefix & suffix in the full name of a magic item.
+    fn magic_name_affixes(&self) -> Option<(Option<&str>, Option<&str>)> {
+        if self.rarity != Rarity::Magic || self.base.is_empty() {
+            return None;
+        }
+        let name = self.name.as_ref()?;
+        let start = name.find(self.base.as_str())?;
+        let prefix = name[..start].trim();
+        let suffix = name[start + self.base.len()..].trim();
+        Some((Some(prefix).filter(|p| !p.is_empty()), Some(suffix).filter(|s| !s.is_empty())))
+    }
+
     /// Iterate over the item mods, if any.
     #[inline]
     pub fn mods<'i>(&'i self) -> impl Iterator<Item=&'i Mod> {
         self.details.as_ref().map(|d| d.mods()).unwrap_or_else(|| Box::new(iter::empty()))
     }
+
+    /// Compute the pseudo mods of this item,
+    /// like "+#% total Elemental Resistance" or "+# total maximum Life".
+    ///
+    /// Pseudo mods aggregate the values of other item mods
+    /// the same way the official trade site does.
+    /// Only the pseudo mods with non-zero values are returned.
+    #[cfg(feature = "mods_db")]
+    pub fn pseudo_mods(&self) -> Vec<Mod> {
+        mods::pseudo::compute(self.mods())
+    }
 }
 // TODO: implement Display that corresponds to the way items are copied to clipboard
 // when you hover over them in game and press Ctrl+C
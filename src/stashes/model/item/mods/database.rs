@@ -1,24 +1,29 @@
 //! Module definining the database of known mods.
 //!
 //! Note that the actual data here is filled in using a build script.
+//! The script also precomputes the lookup keys of mod text templates,
+//! so that the initialization of the database is cheap.
 
-use std::borrow::Cow;
 use std::collections::HashMap;
-use std::error::Error;
+use std::error;
 use std::fmt;
+use std::io;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
-use regex::{self, RegexSet, RegexSetBuilder};
+use itertools::Itertools;
+use serde_json;
 
 use super::ModValues;
-use super::id::{ModId, ModType};
-use super::info::{ModInfo, regex_from_mod_text_template};
+use super::id::{self, ModId, ModType};
+use super::info::{ModInfo, Slot, polarity_variants, tokenize_mod_text};
 
 
 lazy_static! {
-    /// Database of known item mods.
-    pub static ref ITEM_MODS: Database = Database::new().unwrap();
+    /// Database of item mods that's currently used to resolve mod texts.
+    ///
+    /// Initially, this is the database of mods bundled with the crate.
+    static ref ITEM_MODS: RwLock<Arc<Database>> = RwLock::new(Arc::new(Database::bundled()));
 }
 
 /// Initialize the item database at this point in the program.
@@ -29,46 +34,146 @@ pub fn initialize() {
     let _ = &*ITEM_MODS;
 }
 
+/// Returns the database that's currently used to resolve item mods.
+pub fn current() -> Arc<Database> {
+    ITEM_MODS.read().unwrap().clone()
+}
+
+/// Replace the database that's used to resolve item mods
+/// (in `Mod::new` and during `Item` deserialization).
+///
+/// Returns the previous database.
+/// Note that mods which have been resolved already will keep referring to it.
+pub fn replace(database: Database) -> Arc<Database> {
+    let mut current = ITEM_MODS.write().unwrap();
+    ::std::mem::replace(&mut *current, Arc::new(database))
+}
+
 
 /// Structure holding information about all known item mods.
 pub struct Database {
     /// Mapping of mod types -> mod IDs -> mod infos.
     by_type_and_id: HashMap<ModType, HashMap<ModId, Arc<ModInfo>>>,
-    /// Map of `RegexMatcher`s by `ModType`.
+    /// Mapping of mod types -> mod text keys -> mod infos.
     ///
     /// This is used during `Item` deserialization to lookup the mods by their
     /// UI texts (e.g. "+7% increased Maximum Life").
-    matchers_by_type: HashMap<ModType, RegexMatcher<Arc<ModInfo>>>,
+    /// See `ModInfo::key` for how the keys are formed.
+    by_type_and_key: HashMap<ModType, HashMap<String, Vec<Arc<ModInfo>>>>,
+    /// Largest number of text lines that any of the mods takes.
+    max_line_count: usize,
 }
 
 impl Database {
-    /// Create the database and initialize it with known mods.
-    fn new() -> Result<Self, Box<Error>> {
-        lazy_static! {
-            /// Mod text prefixes for the `RegexMatcher` shards.
-            ///
-            /// This is used to divide the space of mods into several buckets/shards
-            /// to speed up the matching of actual mod texts against the known templates.
-            static ref PREFIXES: Vec<String> = include_str!("mod-text-prefixes.txt")
-                .split("\n")
-                .filter(|line| !line.is_empty() && !line.trim_left().starts_with("//"))
-                .map(regex_from_mod_text_template)
-                .map(|p| p.trim_right_matches("$").to_owned())  // `^...$` -> `^...`
-                .collect();
-        }
-
+    /// Create the database of mods that are bundled with the crate.
+    pub fn bundled() -> Self {
         // This is created by the build script from the JSON files in `data/mods`.
-        let by_type_and_id = include!(concat!(
+        let by_type_and_id: HashMap<ModType, HashMap<ModId, Arc<ModInfo>>> = include!(concat!(
             env!("OUT_DIR"), "/", "model/item/mods/database/by_type_and_id.inc.rs"));
-        let matchers_by_type = by_type_and_id.iter()
+        Database::with_mods(by_type_and_id)
+    }
+
+    /// Create the database from stats JSON in the format of PoE trade API
+    /// (https://www.pathofexile.com/api/trade/data/stats).
+    ///
+    /// Mods of types that aren't recognized are skipped.
+    pub fn from_stats_json<R: io::Read>(reader: R) -> Result<Self, LoadError> {
+        let stats: StatsData = serde_json::from_reader(reader)?;
+        Database::from_mods(stats.result.into_iter()
+            .flat_map(|group| group.entries)
+            .map(|entry| (entry.id, entry.text)))
+    }
+
+    /// Create the database from given pairs of mod IDs (e.g. "explicit.stat_3299347043")
+    /// and mod text templates (e.g. "#% increased Attack Speed").
+    ///
+    /// Mods of types that aren't recognized are skipped.
+    pub fn from_mods<I, S, T>(mods: I) -> Result<Self, LoadError>
+        where I: IntoIterator<Item=(S, T)>, S: AsRef<str>, T: AsRef<str>
+    {
+        let mut by_type_and_id = HashMap::new();
+        for (id, text) in mods {
+            let mod_info = match ModInfo::from_raw(id.as_ref(), text.as_ref()) {
+                Ok(mi) => mi,
+                Err(id::Error::UnknownModType(t)) => {
+                    debug!("Skipping mod `{}` of unknown type `{}`", id.as_ref(), t);
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            by_type_and_id.entry(mod_info.id().mod_type()).or_insert_with(HashMap::new)
+                .insert(mod_info.id().clone(), Arc::new(mod_info));
+        }
+        Ok(Database::with_mods(by_type_and_id))
+    }
+
+    /// Create the database from the mapping of mod types -> mod IDs -> mod infos.
+    fn with_mods(by_type_and_id: HashMap<ModType, HashMap<ModId, Arc<ModInfo>>>) -> Self {
+        let by_type_and_key = by_type_and_id.iter()
             .map(|(&mod_type, id2info)| {
-                let matcher = RegexMatcher::with_shards(
-                    PREFIXES.iter().map(|p| p.as_str()),
-                    id2info.values().map(|mi| (mi.regex.as_str(), mi.clone())))?;
-                Ok((mod_type, matcher))
+                let mut key2infos: HashMap<String, Vec<_>> = HashMap::new();
+                for mi in id2info.values() {
+                    key2infos.entry(mi.key().to_owned()).or_insert_with(Vec::new).push(mi.clone());
+                }
+                (mod_type, key2infos)
             })
-            .collect::<Result<HashMap<_, _>, Box<Error>>>()?;
-        Ok(Database{by_type_and_id, matchers_by_type})
+            .collect();
+        let max_line_count = by_type_and_id.values()
+            .flat_map(|id2info| id2info.values().map(|mi| mi.line_count()))
+            .max().unwrap_or(1);
+        Database{by_type_and_id, by_type_and_key, max_line_count}
+    }
+}
+
+/// Structure describing the stats JSON from PoE trade API.
+#[derive(Deserialize)]
+struct StatsData {
+    result: Vec<StatsGroup>,
+}
+#[derive(Deserialize)]
+struct StatsGroup {
+    entries: Vec<StatsEntry>,
+}
+#[derive(Deserialize)]
+struct StatsEntry {
+    id: String,
+    text: String,
+}
+
+
+/// Error when loading the mod `Database`.
+#[derive(Debug)]
+pub enum LoadError {
+    /// Error for when the stats JSON is malformed.
+    Json(serde_json::Error),
+    /// Error for when one of the mod IDs is invalid.
+    ModId(id::Error),
+}
+impl From<serde_json::Error> for LoadError {
+    fn from(input: serde_json::Error) -> Self {
+        LoadError::Json(input)
+    }
+}
+impl From<id::Error> for LoadError {
+    fn from(input: id::Error) -> Self {
+        LoadError::ModId(input)
+    }
+}
+impl error::Error for LoadError {
+    fn description(&self) -> &str { "error loading mod database" }
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            LoadError::Json(ref e) => Some(e),
+            LoadError::ModId(ref e) => Some(e),
+        }
+    }
+}
+impl fmt::Display for LoadError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoadError::Json(ref e) => write!(fmt, "malformed stats JSON: {}", e),
+            LoadError::ModId(ref e) => write!(fmt, "invalid mod: {}", e),
+        }
     }
 }
 
@@ -76,318 +181,129 @@ impl Database {
     /// Returns an iterator over all mods.
     #[inline]
     pub fn iter<'d>(&'d self) -> impl Iterator<Item=&'d ModInfo> + 'd {
-        self.matchers_by_type.values().flat_map(|rm| rm.items().map(|mi| &**mi))
+        self.by_type_and_id.values().flat_map(|id2info| id2info.values().map(|mi| &**mi))
     }
 
     /// Total number of mods in the database.
     #[inline]
     pub fn len(&self) -> usize {
-        self.matchers_by_type.values().map(|rm| rm.count()).sum()
+        self.by_type_and_id.values().map(|id2info| id2info.len()).sum()
+    }
+
+    /// Whether the database contains no mods at all.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
     /// Lookup a mod by its `ModId`.
     #[inline]
-    pub(super) fn lookup(&self, id: ModId) -> Option<Arc<ModInfo>> {
+    pub(super) fn lookup(&self, id: &ModId) -> Option<Arc<ModInfo>> {
         self.by_type_and_id.get(&id.mod_type())
-            .and_then(|id2info| id2info.get(&id))
+            .and_then(|id2info| id2info.get(id))
             .map(|mi| mi.clone())
     }
 
     /// Resolve a mod's actual text on an item.
     ///
     /// Returns the matched `ModInfo` and the values parsed from the text.
+    /// If the text uses the opposite wording to the mod's template
+    /// (e.g. "reduced" rather than "increased"), the corresponding value is negated.
+    ///
+    /// Mods are matched against the templates of `mod_type.template_type()`,
+    /// so e.g. fractured mods resolve to `ModInfo`s of explicit mods.
     pub(super) fn resolve(&self, mod_type: ModType, text: &str) -> Option<(Arc<ModInfo>, ModValues)> {
-        let matcher =  self.matchers_by_type.get(&mod_type)?;
+        let key2infos = self.by_type_and_key.get(&mod_type.template_type())?;
+        let find_matching = |text_variant: &str| -> Vec<_> {
+            let (key, _) = tokenize_mod_text(text_variant);
+            key2infos.get(&key).into_iter().flat_map(|infos| infos.iter())
+                .filter_map(|mi| mi.parse_text(text).map(|values| (mi, values)))
+                .collect()
+        };
 
-        let mut matching_mods = matcher.lookup_all(text);
+        // If the text doesn't match any mod directly, it may be using the opposite wording
+        // (e.g. "reduced" instead of "increased") than the mod template.
+        let mut matching_mods = find_matching(text);
+        if matching_mods.is_empty() {
+            for (flipped_text, _) in polarity_variants(text) {
+                matching_mods = find_matching(&flipped_text);
+                if !matching_mods.is_empty() {
+                    break;
+                }
+            }
+        }
         let match_count = matching_mods.len();
-        let mod_ = match match_count {
+        let (mod_, values) = match match_count {
             0 => { return None; }
             1 => {
-                let mod_ = matching_mods.into_iter().next().unwrap();
+                let (mod_, values) = matching_mods.into_iter().next().unwrap();
                 trace!("Mod text {:?} matched {:?}", text, mod_);
-                mod_
+                (mod_, values)
             }
             n => {
-                // In case of multiple matches, pick the longest one.
-                // This should address picking the correct variants between cases like
-                // "#% increased Attack Damage" vs. "#% increased Attack Damage per 450 Evasion Rating".
+                // In case of multiple matches, pick the one with most values,
+                // and then the one with the longest template.
+                // This addresses templates that differ only by plus signs
+                // or by having constant numbers in place of value placeholders.
                 warn!("Mod text `{}` ({:?}) matched {} mods: {:?}",
-                    text, mod_type, n, matching_mods);
-                matching_mods.sort_by_key(|mi| -(mi.regex.as_str().len() as isize));
+                    text, mod_type, n, matching_mods.iter().map(|&(mi, _)| mi).collect::<Vec<_>>());
+                matching_mods.sort_by_key(|&(mi, _)| {
+                    (-(mi.param_count() as isize), -(mi.text().len() as isize))
+                });
                 matching_mods.into_iter().next().unwrap()
             }
         };
-
-        let values = mod_.parse_text(text)
-            .expect(&format!("mod values for {:?} after parsing by {:?}", text, mod_));
         Some((mod_.clone(), values))
     }
-}
-
-impl fmt::Debug for Database {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(fmt, "Database(<{} mods>)", self.len())
-    }
-}
-
-
-// Mod text matching
-
-/// Matcher from regular expressions to some other arbitrary types.
-///
-/// This is used to match mods texts to `ModInfo`s.
-#[derive(Debug)]
-struct RegexMatcher<T> {
-    /// Shards of the regex matcher.
-    shards: Option<RegexMatcherShard<RegexMatcherShard<T>>>,
-    /// Fallback shard for when the item doesn't match any of the shards.
-    fallback: Option<RegexMatcherShard<T>>,
-}
-
-impl<T> RegexMatcher<T> {
-    /// Create a `RegexMatcher` that doesn't actually match anything.
-    pub fn empty() -> Self {
-        RegexMatcher{shards: None, fallback: None}
-    }
-
-    /// Create a simple `RegexMatcher` that doesn't use sharding.
-    pub fn new<'r, I>(mapping: I) -> Result<Self, Box<Error>>
-        where I: IntoIterator<Item=(&'r str, T)>
-    {
-        Ok(RegexMatcher{
-            shards: None,
-            fallback: Some(RegexMatcherShard::new(mapping)?),
-        })
-    }
 
-    /// Create a `RegexMatcher` that matches regular expressions to some other arbitrary types.
+    /// Resolve consecutive lines of mod text of given type, as found on an item.
     ///
-    /// The regular expressions are sharded into subsets based on their given prefixes
-    /// in order to speed up matching over a large sequence of regexes.
-    pub fn with_shards<'r, P, I>(prefixes: P, mapping: I) -> Result<Self, Box<Error>>
-        where P: IntoIterator<Item=&'r str>,
-              I: IntoIterator<Item=(&'r str, T)>
-    {
-        let mut prefixes: Vec<&str> = prefixes.into_iter().collect();
-        if prefixes.is_empty() {
-            return Self::new(mapping);
-        }
-        // Make sure the prefixes which are themselves prefixes of another prefixes (yo dawg)
-        // are placed later in the prefix list by sorting on descending length.
-        prefixes.sort_by_key(|p| -(p.len() as isize));
-
-        // Create a temporary matcher shard to dispatch the regexes
-        // into their final, prefix-based shards.
-        //
-        // This is a little tricky since we are basically using a regex matcher (for prefixes)
-        // in order to match **regexes** (from `mapping`) so that we can split them into buckets/shards.
-        // (That's why there is a `regex::escape` applied to each prefix).
-        let prefix_regexes: Vec<_> = prefixes.iter()
-            .map(|p| format!("^{}", regex::escape(p)))
-            .collect();
-        let prefix_matcher = RegexMatcherShard::new(
-            prefix_regexes.iter().map(|p| p.as_str()).enumerate().map(|(i, p)| (p, i)))?;
-
-        // Divide the (regex, item) pairs into those shards.
-        let mut shard_splits: Vec<Vec<_>> = (0..prefixes.len()).map(|_| Vec::new()).collect();
-        let mut catchall = Vec::new();
-        for (re, item) in mapping {
-            // Pick the first shard that matches this regex.
-            let shard = match prefix_matcher.lookup_all(re).into_iter().next() {
-                Some(&idx) => {
-                    trace!("Mod regex `{}` goes in shard for prefix `{}`", re, prefixes[idx]);
-                    &mut shard_splits[idx]
-                },
-                None => {
-                    trace!("Mod regex `{}` goes in the fallback shard", re);
-                    &mut catchall
-                },
-            };
-            shard.push((re, item));
-        }
-
-        // Create the final matcher of matchers.
-        //
-        // This is using the same set of prefixes at the outer level,
-        // but now they are actually being used normally, w/o escaping.
-        let prefix_regexes: Vec<Cow<str>> = prefixes.iter()
-            .map(|&p| if p.starts_with("^") { p.into() } else { format!("^{}", p).into() })
-            .collect();
-        let shards_mapping =
-            prefix_regexes.iter().map(|p| &**p).zip(shard_splits.into_iter())
-                // Eliminate empty shards.
-                // Note that this may mean all shards are empty,
-                // in which case we'll only use the `fallback` (if any).
-                .filter(|&(_, ref shard)| !shard.is_empty())
-                .map(|(p, shard)| {
-                    let shard = RegexMatcherShard::new(shard)?;
-                    Ok((p, shard))
-                })
-                .collect::<Result<Vec<_>, Box<Error>>>()?;
-        let shards = if shards_mapping.is_empty() { None } else {
-            Some(RegexMatcherShard::new(shards_mapping)?)
-        };
-
-        // As for the regular expressions that didn't fall into any prefix bucket,
-        // put them into the fallback shard.
-        let fallback = if catchall.is_empty() { None } else {
-            Some(RegexMatcherShard::new(catchall)?)
-        };
-
-        Ok(RegexMatcher{shards, fallback})
-    }
-}
-impl<T> Default for RegexMatcher<T> {
-    fn default() -> Self {
-        RegexMatcher::empty()
-    }
-}
-
-impl<T> RegexMatcher<T> {
-    /// Return the number of regular expressions being matched against.
-    pub fn count(&self) -> usize {
-        let regular_count = match self.shards {
-            Some(ref shards) => shards.items().map(|shard| shard.count()).sum(),
-            None => 0,
-        };
-        regular_count + self.fallback.iter().count()
-    }
-
-    /// Whether a fallback (catch-all) shard is used by this matcher.
-    pub fn has_fallback(&self) -> bool {
-        self.fallback.is_some()
-    }
-
-    /// Return an iterator over all possible items.
-    #[inline]
-    pub fn items<'r>(&'r self) -> impl Iterator<Item=&'r T> + 'r {
-        Box::new(
-            self.shards.iter()
-                .flat_map(|shards| { shards.items().flat_map(|sh| sh.items()) })
-                .chain(self.fallback.iter().flat_map(|fb| fb.items()))
-        )
-    }
-
-    /// Try to match given text against all known patterns
-    /// and return the first match.
-    ///
-    /// Sharded patterns are tried first, followed by the optional fallback.
-    pub fn lookup<'m, 's>(&'m self, text: &'s str) -> Option<&'m T> {
-        self.shards.as_ref().and_then(|shards| {
-                shards.lookup(text).and_then(|sh| sh.lookup(text))
-            }).or_else(|| {
-                self.fallback.as_ref().and_then(|fb| fb.lookup(text))
-            })
-    }
-
-    /// Try to match given text against known patterns
-    /// and return all matches.
-    ///
-    /// Sharded patterns are tried first, and all shards are queried.
-    /// Only if they haven't yielded ANY matches, the optional fallback is then tried.
-    pub fn lookup_all<'m, 's>(&'m self, text: &'s str) -> Vec<&'m T> {
-        let regular_matches = match self.shards {
-            Some(ref shards) => {
-                shards.lookup_all(text).into_iter()
-                    .flat_map(|sh| sh.lookup_all(text))
-                    .collect()
+    /// Some mods span several lines which the API may report as separate mod texts.
+    /// Lines which together match a multi-line mod are grouped,
+    /// so the result contains a number of lines for each resolved mod
+    /// (or for each unresolved line).
+    pub(super) fn resolve_lines<S: AsRef<str>>(
+        &self, mod_type: ModType, lines: &[S]
+    ) -> Vec<(usize, Option<(Arc<ModInfo>, ModValues)>)> {
+        let mut result = Vec::with_capacity(lines.len());
+        let mut i = 0;
+        while i < lines.len() {
+            let max_count = self.max_line_count.min(lines.len() - i);
+            let multi_line = (2..max_count + 1).rev().filter_map(|count| {
+                let text = lines[i..i + count].iter().map(|l| l.as_ref()).join("\n");
+                self.resolve(mod_type, &text)
+                    .filter(|&(ref mi, _)| mi.line_count() == count)
+                    .map(|data| (count, Some(data)))
+            }).next();
+            let (count, data) = multi_line.unwrap_or_else(|| {
+                (1, self.resolve(mod_type, lines[i].as_ref()))
+            });
+            if count > 1 {
+                trace!("Mod text lines {:?} resolved as a single {:?} mod",
+                    &lines[i..i + count].iter().map(|l| l.as_ref()).collect::<Vec<_>>(), mod_type);
             }
-            None => Vec::new(),
-        };
-        if !regular_matches.is_empty() {
-            return regular_matches;
+            result.push((count, data));
+            i += count;
         }
-
-        self.fallback.as_ref().map(|fb| fb.lookup_all(text))
-            .unwrap_or_else(Vec::new)
-    }
-
-    /// Total number of shards used (incl. the possible fallback).
-    pub fn shard_count(&self) -> usize {
-        let regular_shards = self.shards.as_ref().map(|shards| shards.count()).unwrap_or(0);
-        let fallback_shard = if self.has_fallback() { 1 } else { 0 };
-        regular_shards + fallback_shard
+        result
     }
 }
 
-
-/// A single `RegexMatcher` shard.
-#[derive(Debug)]
-struct RegexMatcherShard<T> {
-    /// Regex set for doing the actual matching.
-    regex_set: RegexSet,
-    /// List of items that the regexes map to.
-    items: Vec<T>,
-}
-
-impl<T> RegexMatcherShard<T> {
-    /// Create a new `RegexMatcherShard` given a mapping in the form of iterable of pairs.
-    #[inline]
-    pub fn new<'r, I>(mapping: I) -> Result<Self, Box<Error>>
-        where I: IntoIterator<Item=(&'r str, T)>
-    {
-        let mut regexes = Vec::new();
-        let mut items = Vec::new();
-        for (regex, item) in mapping.into_iter() {
-            regexes.push(regex);
-            items.push(item);
-        }
-
-        let regex_set = RegexSetBuilder::new(regexes)
-            .case_insensitive(true)
-            .size_limit(SHARD_REGEXSET_SIZE_LIMIT_BYTES)
-            // TODO: .dfa_size_limit() too?
-            .build()?;
-        Ok(RegexMatcherShard{regex_set, items})
-    }
-}
-
-/// Size limit for a single compiled set of regular expressions
-/// in the `RegexMatcherShard`.
-///
-/// *Note*: If this limits gets exceeded, it is preferrable to adjust the sharding
-/// (in mods-text-prefixes.txt) rather than raising it.
-const SHARD_REGEXSET_SIZE_LIMIT_BYTES: usize = 8 * 1024 * 1024;
-
-impl<T> RegexMatcherShard<T> {
-    /// Return the number of regular expressions being matched against by this shard.
-    #[inline]
-    pub fn count(&self) -> usize {
-        self.regex_set.len()
-    }
-
-    /// Return an iterator over the possible items.
-    #[inline]
-    pub fn items<'r>(&'r self) -> impl Iterator<Item=&'r T> + 'r {
-        Box::new(self.items.iter())
-    }
-
-    /// Try to match given text against all regular expressions in the shard,
-    /// and return a reference to the corresponding item that matched (if any).
-    pub fn lookup<'m, 's>(&'m self, text: &'s str) -> Option<&'m T> {
-        let matched = self.lookup_all(text);
-        if matched.len() > 1 {
-            warn!("Ambiguous text in RegexMatcherShard::lookup(): {:?} (matched {} items)",
-                text, matched.len());
-            return None;
-        }
-        matched.into_iter().next()
-    }
-
-    /// Try to match given text against all regular expressions in the shard.
-    /// Returns a reference to all items that matched.
-    pub fn lookup_all<'m, 's>(&'m self, text: &'s str) -> Vec<&'m T> {
-        self.regex_set.matches(text).iter().map(|idx| &self.items[idx]).collect()
+impl fmt::Debug for Database {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(fmt, "Database(<{} mods>)", self.len())
     }
 }
 
 
 #[cfg(test)]
+#[allow(non_snake_case)]
 mod tests {
+    use std::sync::Arc;
     use std::time::Instant;
-    use super::super::id::ModType;
-    use super::{initialize, ITEM_MODS};
+    use super::super::id::{ModId, ModType};
+    use super::super::info::tokenize_mod_template;
+    use super::{current, initialize, replace, Database};
 
     #[test]
     fn initialize_actually_initializes() {
@@ -408,15 +324,111 @@ mod tests {
 
     #[test]
     fn item_mods_db_is_valid() {
-        assert!(ITEM_MODS.len() > 0);
+        assert!(current().len() > 0);
+    }
+
+    #[test]
+    fn precompiled_keys_match_tokenizer() {
+        for mi in current().iter() {
+            let (key, slots) = tokenize_mod_template(mi.text().trim());
+            assert_eq!(key, mi.key(), "key of mod {:?}", mi.id());
+            assert_eq!(slots.as_slice(), mi.slots(), "slots of mod {:?}", mi.id());
+        }
     }
 
     #[test]
     fn all_item_mod_types_in_db() {
-        // Check that we have loaded mods of all types.
-        for mt in ModType::iter_variants() {
-            let mod_count = ITEM_MODS.iter().filter(|mi| mi.id().mod_type() == mt).count();
+        // Check that we have loaded mods of all types that have their own templates
+        // (veiled mods don't have any).
+        let has_templates = |mt: &ModType| mt.template_type() == *mt && *mt != ModType::Veiled;
+        for mt in ModType::iter_variants().filter(has_templates) {
+            let mod_count = current().iter().filter(|mi| mi.id().mod_type() == mt).count();
             assert!(mod_count > 0);
         }
     }
+
+    #[test]
+    fn from_stats_json() {
+        let json = r##"{"result": [
+            {"label": "Explicit", "entries": [
+                {"id": "explicit.stat_3299347043", "text": "# to maximum Life", "type": "explicit"},
+                {"id": "explicit.stat_42", "text": "Has #\nSockets", "type": "explicit"},
+                {"id": "monster.stat_1", "text": "Monsters are #% Awesome", "type": "monster"}
+            ]}
+        ]}"##;
+        let db = Database::from_stats_json(json.as_bytes()).unwrap();
+        assert_eq!(2, db.len());
+
+        let (mi, values) = db.resolve(ModType::Explicit, "+42 to maximum Life").unwrap();
+        assert_eq!(&ModId::new(ModType::Explicit, 3299347043), mi.id());
+        assert_eq!(42.0, values[0]);
+        assert!(db.resolve(ModType::Explicit, "Has 3 Sockets").is_some());
+        assert!(db.resolve(ModType::Implicit, "+42 to maximum Life").is_none());
+
+        // Fractured mods are matched against explicit mod templates.
+        let (mi, _) = db.resolve(ModType::Fractured, "+42 to maximum Life").unwrap();
+        assert_eq!(&ModId::new(ModType::Explicit, 3299347043), mi.id());
+    }
+
+    #[test]
+    fn resolve_opposite_polarity() {
+        let db = Database::from_mods(vec![
+            ("explicit.stat_1", "#% increased Attack Speed"),
+            ("explicit.stat_2", "#% reduced Mana Cost of Skills"),
+            ("explicit.stat_3", "# to maximum Life"),
+        ]).unwrap();
+
+        let (mi, values) = db.resolve(ModType::Explicit, "8% reduced Attack Speed").unwrap();
+        assert_eq!(&ModId::new(ModType::Explicit, 1), mi.id());
+        assert_eq!(-8.0, values[0]);
+        let (mi, values) = db.resolve(ModType::Explicit, "5% increased Mana Cost of Skills").unwrap();
+        assert_eq!(&ModId::new(ModType::Explicit, 2), mi.id());
+        assert_eq!(-5.0, values[0]);
+        let (_, values) = db.resolve(ModType::Explicit, "-20 to maximum Life").unwrap();
+        assert_eq!(-20.0, values[0]);
+        assert!(db.resolve(ModType::Explicit, "8% reduced Cast Speed").is_none());
+    }
+
+    #[test]
+    fn resolve_lines() {
+        let db = Database::from_mods(vec![
+            ("explicit.stat_1", "Immunity to Shock during Flask effect\nRemoves Shock on use"),
+            ("explicit.stat_2", "#% increased Charge Recovery"),
+            ("explicit.stat_3", "Removes Shock on use"),
+        ]).unwrap();
+
+        let resolved = db.resolve_lines(ModType::Explicit, &[
+            "Immunity to Shock during Flask effect",
+            "Removes Shock on use",
+            "20% increased Charge Recovery",
+            "Removes Shock on use",
+            "Something completely different",
+        ]);
+        let ids: Vec<_> = resolved.iter()
+            .map(|&(count, ref data)| (count, data.as_ref().map(|&(ref mi, _)| mi.id().clone())))
+            .collect();
+        assert_eq!(vec![
+            (2, Some(ModId::new(ModType::Explicit, 1))),
+            (1, Some(ModId::new(ModType::Explicit, 2))),
+            (1, Some(ModId::new(ModType::Explicit, 3))),
+            (1, None),
+        ], ids);
+        assert!(db.resolve(ModType::Explicit,
+            "Immunity to Shock during Flask effect\nRemoves Shock on use").is_some());
+    }
+
+    #[test]
+    fn from_stats_json__malformed() {
+        assert!(Database::from_stats_json("[]".as_bytes()).is_err());
+        let json = r##"{"result": [{"entries": [{"id": "42", "text": "#"}]}]}"##;
+        assert!(Database::from_stats_json(json.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn replace_database() {
+        let previous = current();
+        replace(Database::bundled());
+        assert!(!Arc::ptr_eq(&previous, &current()));
+        assert_eq!(previous.len(), current().len());
+    }
 }
@@ -12,18 +12,35 @@ use regex::Regex;
 use serde::de::{self, Deserialize, Deserializer, Unexpected};
 
 
-/// Mod identifier, including it's type (crafted, implicit, etc.) and a unique number.
-#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+/// Mod identifier, including it's type (crafted, implicit, etc.)
+/// and a unique number or name.
+#[derive(Clone, Eq, PartialEq, Hash)]
 pub struct ModId {
     type_: ModType,
-    number: u64,
+    stat: Stat,
+}
+
+/// The part of mod identifier that's unique within the mod type.
+///
+/// Most mods are identified by numbers (as in "explicit.stat_3299347043")
+/// but some, like pseudo mods, have names instead ("pseudo.pseudo_total_life").
+#[derive(Clone, Eq, PartialEq, Hash)]
+enum Stat {
+    Number(u64),
+    Name(String),
 }
 
 impl ModId {
     /// Create mod identifier of given type and number.
     #[inline]
     pub fn new(type_: ModType, number: u64) -> Self {
-        ModId { type_, number }
+        ModId { type_, stat: Stat::Number(number) }
+    }
+
+    /// Create mod identifier of given type and name.
+    #[inline]
+    pub fn with_name<N: Into<String>>(type_: ModType, name: N) -> Self {
+        ModId { type_, stat: Stat::Name(name.into()) }
     }
 }
 
@@ -33,12 +50,15 @@ impl FromStr for ModId {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         lazy_static! {
             static ref MOD_ID_RE: Regex = Regex::new(
-                r#"(?P<type>\w+)\.stat_(?P<number>\d+)"#
+                r#"(?P<type>\w+)\.(?:stat_(?P<number>\d+)|(?P<name>\w+))"#
             ).unwrap();
         }
         let caps = MOD_ID_RE.captures(s).ok_or_else(|| Error::Malformed(s.to_owned()))?;
         let type_ = ModType::from_str(caps.name("type").unwrap().as_str())
             .map_err(|Unrepresentable(t)| Error::UnknownModType(t))?;
+        if let Some(name) = caps.name("name") {
+            return Ok(ModId::with_name(type_, name.as_str()));
+        }
         let number = {
             let n = caps.name("number").unwrap().as_str();
             n.parse().map_err(|_| Error::InvalidModNumber(n.to_owned()))?
@@ -53,7 +73,7 @@ impl<'de> Deserialize<'de> for ModId {
         let s: String = Deserialize::deserialize(deserializer)?;
         ModId::from_str(&s).map_err(|e| match e {
             Error::Malformed(s) => de::Error::invalid_value(
-                Unexpected::Str(s.as_str()), &"mod ID as $TYPE.stat_$NUMBER"),
+                Unexpected::Str(s.as_str()), &"mod ID as $TYPE.stat_$NUMBER or $TYPE.$NAME"),
             Error::UnknownModType(ref t) => de::Error::unknown_variant(t.as_str(), MOD_TYPES),
             Error::InvalidModNumber(ref n) => de::Error::invalid_value(
                 Unexpected::Str(n.as_str()), &"mod number (as unsigned integer)"),
@@ -68,15 +88,31 @@ impl ModId {
         self.type_
     }
 
-    /// Mod's identifying number (unique within mods of the same type).
-    pub fn mod_number(&self) -> u64 {
-        self.number
+    /// Mod's identifying number (unique within mods of the same type),
+    /// if the mod is identified by a number.
+    pub fn mod_number(&self) -> Option<u64> {
+        match self.stat {
+            Stat::Number(n) => Some(n),
+            Stat::Name(_) => None,
+        }
+    }
+
+    /// Mod's identifying name (unique within mods of the same type),
+    /// if the mod is identified by a name.
+    pub fn mod_name(&self) -> Option<&str> {
+        match self.stat {
+            Stat::Number(_) => None,
+            Stat::Name(ref n) => Some(n.as_str()),
+        }
     }
 }
 
 impl fmt::Debug for ModId {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmt, "ModId::new({:?}, {:?})", self.type_, self.number)
+        match self.stat {
+            Stat::Number(n) => write!(fmt, "ModId::new({:?}, {:?})", self.type_, n),
+            Stat::Name(ref n) => write!(fmt, "ModId::with_name({:?}, {:?})", self.type_, n),
+        }
     }
 }
 
@@ -117,8 +153,42 @@ macro_attr! {
         Enchant,
         /// Explicit ("regular") item mods
         Explicit,
+        /// Fractured explicit mods, which cannot be changed by crafting.
+        ///
+        /// These use the same templates as `Explicit` mods.
+        Fractured,
         /// Implicit item mods (inherent to the item base).
+        ///
+        /// Note that implicits of corrupted items may have been changed by the corruption,
+        /// but the API doesn't distinguish them from regular implicits in any way.
         Implicit,
+        /// Pseudo mods that aggregate other mods of an item
+        /// (e.g. total resistances), like on the official trade site.
+        Pseudo,
+        /// Implicit mods of synthesised items, which replace those of the item base.
+        ///
+        /// These use the same templates as `Implicit` mods.
+        Synthesised,
+        /// Veiled mods which are revealed by unveiling them at Jun's crafting bench.
+        ///
+        /// These don't have any templates, as their texts are just placeholders
+        /// ("Veiled Prefix" or "Veiled Suffix") until they're unveiled.
+        Veiled,
+    }
+}
+
+impl ModType {
+    /// Type of the mods whose text templates are used for mods of this type.
+    ///
+    /// This is the type itself, except for `Fractured` mods
+    /// which are just `Explicit` mods that have been locked in place,
+    /// and `Synthesised` mods which are `Implicit` mods coming from synthesis.
+    pub fn template_type(&self) -> ModType {
+        match *self {
+            ModType::Fractured => ModType::Explicit,
+            ModType::Synthesised => ModType::Implicit,
+            mt => mt,
+        }
     }
 }
 
@@ -130,19 +200,25 @@ impl FromStr for ModType {
             "crafted" => Ok(ModType::Crafted),
             "enchant" => Ok(ModType::Enchant),
             "explicit" => Ok(ModType::Explicit),
+            "fractured" => Ok(ModType::Fractured),
             "implicit" => Ok(ModType::Implicit),
+            "pseudo" => Ok(ModType::Pseudo),
+            "synthesised" => Ok(ModType::Synthesised),
+            "veiled" => Ok(ModType::Veiled),
             _ => Err(Unrepresentable(s.to_owned())),
         }
     }
 }
 
-const MOD_TYPES: &[&str] = &["crafted", "enchant", "explicit", "implicit"];
+const MOD_TYPES: &[&str] = &[
+    "crafted", "enchant", "explicit", "fractured", "implicit", "pseudo", "synthesised", "veiled",
+];
 
 
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
-    use super::{ModType, MOD_TYPES};
+    use super::{ModId, ModType, MOD_TYPES};
 
     #[test]
     fn mod_type_strings() {
@@ -150,4 +226,20 @@ mod tests {
             assert!(ModType::from_str(type_).is_ok());
         }
     }
+
+    #[test]
+    fn mod_id_with_number() {
+        let id = ModId::from_str("explicit.stat_3299347043").unwrap();
+        assert_eq!(ModType::Explicit, id.mod_type());
+        assert_eq!(Some(3299347043), id.mod_number());
+        assert_eq!(None, id.mod_name());
+    }
+
+    #[test]
+    fn mod_id_with_name() {
+        let id = ModId::from_str("pseudo.pseudo_total_life").unwrap();
+        assert_eq!(ModType::Pseudo, id.mod_type());
+        assert_eq!(None, id.mod_number());
+        assert_eq!(Some("pseudo_total_life"), id.mod_name());
+    }
 }
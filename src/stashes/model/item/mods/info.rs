@@ -1,15 +1,13 @@
 //! Module definining the item mod info type.
 
 use std::cmp::{Eq, PartialEq};
-use std::error::Error;
 use std::fmt;
 use std::str::FromStr;
 
-use regex::{self, Regex, RegexBuilder};
-
 use util::parse_number;
-use super::ModValues;
-use super::id::ModId;
+use super::{ModValue, ModValues};
+use super::id::{self, ModId};
+use super::tokenize::{tokenize, Number};
 
 
 /// Information about a single known item mod.
@@ -17,59 +15,159 @@ use super::id::ModId;
 pub struct ModInfo {
     /// Identifier of the mod.
     ///
-    /// This is parsed from a string such as "implicit.stat_587431675".
+    /// This is parsed from a string such as "implicit.stat_587431675"
+    /// (or "pseudo.pseudo_total_life" for pseudo mods).
     id: ModId,
     /// Template for the mod text.
     ///
     /// This contains the placeholders for mod values, e.g. "+# to maximum Life".
     text_template: String,
-    /// Regular expression matching the textual form of the mod
-    /// as it appears on items.
+    /// Key used to look up the mod by its textual form as it appears on items.
+    ///
+    /// This is the lowercase text template where all the numbers
+    /// (both mod values and constants) are replaced with `#`, e.g. "# to maximum life".
+    key: String,
+    /// Numeric slots of the text template, in the order they appear in the text.
+    slots: Vec<Slot>,
+}
+
+/// A place in mod text template where a number occurs.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum Slot {
+    /// Placeholder for a mod value, e.g. "#" in "#% increased Attack Speed".
     ///
-    /// Within the regex, indexed (unnamed) capture groups are expected to be filled
-    /// by specific mod values, e.g. "(\d+)% increased Global Critical Strike Chance".
-    pub(super) regex: Regex,
+    /// Values may always be preceded by a plus sign;
+    /// if `plus` is true, the sign is required.
+    Value { plus: bool },
+    /// Number that's a constant part of the mod text, e.g. "1" in "Has 1 Abyssal Socket".
+    Constant { number: String, plus: bool },
 }
 
 impl ModInfo {
     /// Create `ModInfo` from given mod ID and its text (as obtained from the PoE API).
-    pub(super) fn from_raw<T: Into<String>>(id: &str, text: T) -> Result<Self, Box<Error>> {
+    pub(super) fn from_raw<T: Into<String>>(id: &str, text: T) -> Result<Self, id::Error> {
         let id = ModId::from_str(id)?;
         let text = text.into();
-        let regex_str = regex_from_mod_text_template(&text);
-        Ok(ModInfo {
+        let (key, slots) = tokenize_mod_template(text.trim());
+        Ok(ModInfo::precompiled(id, text, key, slots))
+    }
+
+    /// Create `ModInfo` from its parts that have been computed beforehand
+    /// (usually by the build script).
+    pub(super) fn precompiled<T, K>(id: ModId, text: T, key: K, slots: Vec<Slot>) -> Self
+        where T: Into<String>, K: Into<String>
+    {
+        ModInfo {
             id: id,
-            text_template: text,
-            regex: RegexBuilder::new(&regex_str).case_insensitive(true).build()?,
-        })
+            text_template: text.into(),
+            key: key.into(),
+            slots: slots,
+        }
     }
 }
 
-/// Convert a mod text template from PoE API (like "#% increased Life")
-/// to a regular expression that can match the actual mod texts
-/// (something like "^(\d+)% increased Life$").
-pub(super) fn regex_from_mod_text_template(text: &str) -> String {
-    /// Regex for integer or float values that occur in mod texts.
-    const VALUE_RE: &str = r"\+?(\d+(?:\.\d+)?)";
-    lazy_static! {
-        /// Placeholder for mod value in the text template.
-        /// It is replaced with `VALUE_RE` when converting mod text template to a regex.
-        static ref VALUE_PH: String = regex::escape("#");
-    }
-     format!("^{}$", regex::escape(text.trim()).replace(VALUE_PH.as_str(), VALUE_RE))
+/// Split mod text template into its lookup key and the numeric slots.
+///
+/// The key is lowercased text where every number or value placeholder
+/// (with its optional plus sign) is replaced with `#`.
+///
+/// The build script uses the same `tokenize` function to precompile the bundled mods.
+pub(super) fn tokenize_mod_template(template: &str) -> (String, Vec<Slot>) {
+    let (key, numbers) = tokenize(template, true);
+    let slots = numbers.into_iter().map(|n| match n {
+        Number{digits: None, pluses} => Slot::Value{plus: pluses > 0},
+        Number{digits: Some(number), pluses} => Slot::Constant{number, plus: pluses > 0},
+    }).collect();
+    (key, slots)
+}
+
+/// Split actual mod text into its lookup key and the numbers it contains.
+///
+/// The key is compatible with the one produced by `tokenize_mod_template`.
+pub(super) fn tokenize_mod_text(text: &str) -> (String, Vec<Number>) {
+    tokenize(text, false)
+}
+
+/// Pairs of words with opposite meanings that are used in mod texts,
+/// e.g. "10% reduced Attack Speed" is the same as "-10% increased Attack Speed".
+///
+/// The boolean flag tells whether the word refers to the number following it
+/// (as in "lose 5% of Energy Shield") rather than preceding it (as in "5% less Damage").
+const POLARITY_WORDS: &[(&str, &str, bool)] = &[
+    ("increased", "reduced", false),
+    ("more", "less", false),
+    ("gain", "lose", true),
+    ("gains", "loses", true),
+];
+
+/// Produce variants of mod text with the polarity of a single value flipped,
+/// e.g. "10% reduced Attack Speed" -> "10% increased Attack Speed"
+/// or "-10 to maximum Life" -> "+10 to maximum Life".
+///
+/// Each variant is returned along with the index of the number in the text
+/// whose polarity has been flipped.
+pub(super) fn polarity_variants(text: &str) -> Vec<(String, usize)> {
+    let number_count = |prefix: &str| tokenize_mod_text(prefix).1.len();
+    let mut result = Vec::new();
+
+    let mut start = None;
+    for (i, c) in text.char_indices().chain(Some((text.len(), ' '))) {
+        if c.is_alphanumeric() {
+            if start.is_none() { start = Some(i); }
+            continue;
+        }
+        let word_start = match start.take() { Some(s) => s, None => continue };
+        let word = text[word_start..i].to_lowercase();
+        for &(positive, negative, refers_forward) in POLARITY_WORDS {
+            let opposite = if word == positive { negative }
+                else if word == negative { positive }
+                else { continue };
+            let preceding = number_count(&text[..word_start]);
+            let number_index = match (refers_forward, preceding) {
+                (false, 0) | (true, _) => preceding,  // the next number
+                (false, n) => n - 1,
+            };
+            result.push((format!("{}{}{}", &text[..word_start], opposite, &text[i..]), number_index));
+        }
+    }
+
+    // Minus signs before numbers (but not in ranges like "1-2").
+    for (i, _) in text.match_indices('-') {
+        let is_sign = text[..i].chars().next_back().map(|c| !c.is_alphanumeric()).unwrap_or(true)
+            && text[i + 1..].chars().next().map(|c| c.is_ascii_digit()).unwrap_or(false);
+        if is_sign {
+            result.push((format!("{}+{}", &text[..i], &text[i + 1..]), number_count(&text[..i])));
+        }
+    }
+    result
 }
 
 impl ModInfo {
     /// ID of the mod.
     #[inline]
-    pub fn id(&self) -> ModId {
-        self.id
+    pub fn id(&self) -> &ModId {
+        &self.id
+    }
+
+    /// Key used to look up the mod by its text on an item.
+    #[inline]
+    pub(super) fn key(&self) -> &str {
+        self.key.as_str()
+    }
+
+    /// Numeric slots of the text template.
+    #[cfg(test)]
+    pub(super) fn slots(&self) -> &[Slot] {
+        self.slots.as_slice()
     }
 
     /// Expected format of the mod's text on an item.
     ///
     /// This is a string that in most cases contains hashes (`#`) as placeholders
     /// for mod's numerical, e.g. "+#% increased Attack Speed".
+    ///
+    /// Templates of mods that span several lines (see `line_count`)
+    /// keep their line breaks rather than having them joined with spaces.
     #[inline]
     pub fn text(&self) -> &str {
         self.text_template.as_str()
@@ -82,22 +180,71 @@ impl ModInfo {
     /// (e.g. "Adds # to # Lightning Damage").
     #[inline]
     pub fn param_count(&self) -> usize {
-        self.regex.captures_len() - 1  // -1 because the entire regex match is counted here
+        self.slots.iter().filter(|s| match **s { Slot::Value{..} => true, _ => false }).count()
+    }
+
+    /// Returns how many lines of text the mod takes in the game UI.
+    ///
+    /// Most mods are single-line, but some (e.g. "Immunity to Shock during Flask effect"
+    /// with "Removes Shock on use") span several lines that the API may report
+    /// as separate mod texts.
+    #[inline]
+    pub fn line_count(&self) -> usize {
+        self.text_template.lines().count().max(1)
     }
 
     /// Parse mod text from an actual item and return the corresponding mod values.
     ///
+    /// The text may use the opposite wording to the mod's template
+    /// (like "10% reduced Attack Speed" for "#% increased Attack Speed"),
+    /// in which case the corresponding value is negated.
+    ///
     /// If the text doesn't match this mod, `None` is returned.
     pub fn parse_text(&self, text: &str) -> Option<ModValues> {
-        if self.param_count() == 0 {
-            return Some(ModValues::none());
+        let text = text.trim();
+        if let Some(values) = self.parse_exact_text(text) {
+            return Some(values.into_iter().collect());
         }
-        self.regex.captures(text.trim()).map(|caps| {
-            caps.iter().skip(1)
-                .map(Option::unwrap)  // if there was a match, then every group caught a value
-                .map(|m| parse_number(m.as_str()).unwrap())  // and they were all numbers
-                .collect()
-        })
+        for (flipped_text, number_index) in polarity_variants(text) {
+            let value_index = match self.slots.get(number_index) {
+                Some(&Slot::Value{..}) => self.slots[..number_index].iter()
+                    .filter(|s| match **s { Slot::Value{..} => true, _ => false })
+                    .count(),
+                _ => continue,  // only mod values can be negated
+            };
+            if let Some(mut values) = self.parse_exact_text(&flipped_text) {
+                values[value_index] = -values[value_index];
+                return Some(values.into_iter().collect());
+            }
+        }
+        None
+    }
+
+    /// Parse mod text which has the exact same wording as the mod's template.
+    fn parse_exact_text(&self, text: &str) -> Option<Vec<ModValue>> {
+        let (key, numbers) = tokenize_mod_text(text);
+        if key != self.key || numbers.len() != self.slots.len() {
+            return None;
+        }
+        let mut values = Vec::with_capacity(numbers.len());
+        for (slot, found) in self.slots.iter().zip(numbers) {
+            let digits = found.digits.unwrap();  // there are no placeholders in mod texts
+            match *slot {
+                Slot::Value{plus} => {
+                    let min_pluses = if plus { 1 } else { 0 };
+                    if found.pluses < min_pluses || found.pluses > min_pluses + 1 {
+                        return None;
+                    }
+                    values.push(parse_number(&digits).unwrap());  // it's all digits
+                }
+                Slot::Constant{ref number, plus} => {
+                    if *number != digits || found.pluses != plus as usize {
+                        return None;
+                    }
+                }
+            }
+        }
+        Some(values)
     }
 
     /// Format mod text using provided mod values.
@@ -154,7 +301,7 @@ mod tests {
         let mod_info = ModInfo::from_raw(MOD_ID, MOD_TEXT_TEMPLATE).unwrap();
 
         assert_eq!(ModType::Explicit, mod_info.id().mod_type());
-        assert_eq!(42, mod_info.id().mod_number());
+        assert_eq!(Some(42), mod_info.id().mod_number());
         assert_eq!(MOD_TEXT_TEMPLATE, mod_info.text());
     }
 
@@ -174,6 +321,66 @@ mod tests {
         assert_eq!(MOD_VALUE, values[0]);
     }
 
+    #[test]
+    fn parse_text__not_matching() {
+        let mod_info = ModInfo::from_raw(MOD_ID, MOD_TEXT_TEMPLATE).unwrap();
+        assert!(mod_info.parse_text("+69% increased Laziness").is_none());
+        assert!(mod_info.parse_text(NO_VALUES_MOD_TEXT).is_none());
+    }
+
+    #[test]
+    fn parse_text__plus_signs() {
+        let optional_plus = ModInfo::from_raw(MOD_ID, "#% to Awesomeness").unwrap();
+        assert_eq!(42.0, optional_plus.parse_text("42% to Awesomeness").unwrap()[0]);
+        assert_eq!(42.0, optional_plus.parse_text("+42% to Awesomeness").unwrap()[0]);
+
+        let required_plus = ModInfo::from_raw(MOD_ID, "+#% to Awesomeness").unwrap();
+        assert!(required_plus.parse_text("42% to Awesomeness").is_none());
+        assert_eq!(42.0, required_plus.parse_text("+42% to Awesomeness").unwrap()[0]);
+    }
+
+    #[test]
+    fn parse_text__constant_numbers() {
+        let mod_info = ModInfo::from_raw(MOD_ID, "Has 1 Awesome Socket per # Sockets").unwrap();
+        assert_eq!(1, mod_info.param_count());
+        assert_eq!(3.0, mod_info.parse_text("Has 1 Awesome Socket per 3 Sockets").unwrap()[0]);
+        assert!(mod_info.parse_text("Has 2 Awesome Socket per 3 Sockets").is_none());
+    }
+
+    #[test]
+    fn parse_text__multiple_lines() {
+        let mod_info = ModInfo::from_raw(MOD_ID, "#% more Awesomeness\nwhile Awesome").unwrap();
+        assert_eq!(2, mod_info.line_count());
+        assert_eq!(42.0, mod_info.parse_text("42% more Awesomeness\nwhile Awesome").unwrap()[0]);
+        assert_eq!(42.0, mod_info.parse_text("42% more Awesomeness while Awesome").unwrap()[0]);
+    }
+
+    #[test]
+    fn parse_text__opposite_polarity() {
+        let mod_info = ModInfo::from_raw(MOD_ID, "#% increased Awesomeness").unwrap();
+        assert_eq!(-42.0, mod_info.parse_text("42% reduced Awesomeness").unwrap()[0]);
+        let mod_info = ModInfo::from_raw(MOD_ID, "#% more Awesomeness per # Charges").unwrap();
+        let values = mod_info.parse_text("42% less Awesomeness per 3 Charges").unwrap();
+        assert_eq!((-42.0, 3.0), (values[0], values[1]));
+        let mod_info = ModInfo::from_raw(MOD_ID, "Gain #% of Awesomeness").unwrap();
+        assert_eq!(-42.0, mod_info.parse_text("Lose 42% of Awesomeness").unwrap()[0]);
+        let mod_info = ModInfo::from_raw(MOD_ID, "+# to Awesomeness").unwrap();
+        assert_eq!(-42.0, mod_info.parse_text("-42 to Awesomeness").unwrap()[0]);
+    }
+
+    #[test]
+    fn parse_text__opposite_polarity_of_constant() {
+        let mod_info = ModInfo::from_raw(MOD_ID, "#% chance to gain 1 Awesomeness").unwrap();
+        assert!(mod_info.parse_text("42% chance to lose 1 Awesomeness").is_none());
+    }
+
+    #[test]
+    fn parse_text__case_insensitive() {
+        let mod_info = ModInfo::from_raw(MOD_ID, MOD_TEXT_TEMPLATE).unwrap();
+        let values = mod_info.parse_text(&MOD_TEXT.to_uppercase()).unwrap();
+        assert_eq!(MOD_VALUE, values[0]);
+    }
+
     #[test]
     #[should_panic(expected = "Invalid number of mod values")]
     fn format_text__no_values__but_some_given() {
@@ -2,12 +2,18 @@
 
                             mod id;
                             mod info;
-#[cfg(feature = "mods_db")] mod database;
-#[cfg(test)]                mod tests;
+#[cfg(feature = "mods_db")] pub(super) mod database;
+#[cfg(feature = "mods_db")] pub(super) mod pseudo;
+#[cfg(feature = "mods_db")] pub(super) mod tier;
+                            mod tokenize;
                             mod values;
 
-pub use self::id::{ModId, ModType};
+#[cfg(feature = "mods_db")]
+pub use self::database::{Database, LoadError as DatabaseLoadError};
+pub use self::id::{Error as ModIdError, ModId, ModType};
 pub use self::info::ModInfo;
+#[cfg(feature = "mods_db")]
+pub use self::tier::{Affix, AffixStat, AffixType, ModTier};
 pub use self::values::{ModValue, ModValues};
 
 
@@ -33,12 +39,47 @@ impl Mod {
 
         // Resolve mod text against item mod database if it's available.
         #[cfg(feature = "mods_db")]
-        let data = database::ITEM_MODS.resolve(type_, &text);
+        let data = database::current().resolve(type_, &text);
         #[cfg(not(feature = "mods_db"))]
         let data = None;
 
         Mod{type_, text, data}
     }
+
+    /// Create `Mod`s from consecutive lines of mod text that are found on an item.
+    ///
+    /// Unlike calling `Mod::new` for every line, this recognizes mods
+    /// which span multiple lines, and creates a single `Mod` for all of them.
+    pub fn from_lines<I, T>(type_: ModType, lines: I) -> Vec<Self>
+        where I: IntoIterator<Item=T>, T: Into<String>
+    {
+        let lines: Vec<String> = lines.into_iter().map(Into::into).collect();
+
+        #[cfg(feature = "mods_db")]
+        {
+            let resolved = database::current().resolve_lines(type_, &lines);
+            let mut lines = lines.into_iter();
+            resolved.into_iter()
+                .map(|(count, data)| {
+                    let text = lines.by_ref().take(count).collect::<Vec<_>>().join("\n");
+                    Mod{type_, text, data}
+                })
+                .collect()
+        }
+        #[cfg(not(feature = "mods_db"))]
+        {
+            lines.into_iter().map(|text| Mod{type_, text, data: None}).collect()
+        }
+    }
+
+    /// Create `Mod` of a known kind, with given values.
+    ///
+    /// The mod text is formatted from the `ModInfo` template.
+    #[cfg(feature = "mods_db")]
+    fn from_info(type_: ModType, info: Arc<ModInfo>, values: ModValues) -> Self {
+        let text = info.format_text(&values);
+        Mod{type_, text, data: Some((info, values))}
+    }
 }
 
 impl Mod {
@@ -74,6 +115,21 @@ impl Mod {
     pub fn as_str(&self) -> &str {
         self.text.as_str()
     }
+
+    /// Lines of the mod text, as displayed in the game UI.
+    #[inline]
+    pub fn lines<'m>(&'m self) -> Box<Iterator<Item=&'m str> + 'm> {
+        Box::new(self.text.lines())
+    }
+
+    /// Number of lines the mod text takes.
+    ///
+    /// This is more than one if the mod spans several lines
+    /// that may have been reported as separate mods by the API.
+    #[inline]
+    pub fn line_count(&self) -> usize {
+        self.lines().count().max(1)
+    }
 }
 
 impl fmt::Debug for Mod {
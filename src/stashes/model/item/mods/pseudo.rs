@@ -0,0 +1,326 @@
+//! Module computing pseudo mods of an item.
+//!
+//! Pseudo mods (like "+#% total Elemental Resistance") are what the official trade site
+//! uses to aggregate the values of several different mods on an item.
+//! Their texts come from `data/mods/pseudo.json` while the rules for computing them
+//! are defined below.
+
+use std::collections::HashMap;
+
+use super::{Mod, ModValue};
+use super::database;
+use super::id::{ModId, ModType};
+
+
+/// Rule for computing the values of a single pseudo mod.
+enum Rule {
+    /// Sum of values of mods with given text templates,
+    /// each multiplied by the accompanying factor.
+    Sum(&'static [(&'static str, ModValue)]),
+    /// Sum of values of other (previously defined) pseudo mods.
+    Total(&'static [&'static str]),
+    /// Number of other (previously defined) pseudo mods that have non-zero values.
+    Count(&'static [&'static str]),
+}
+
+/// Rules for computing pseudo mods, keyed by pseudo mod names.
+///
+/// Rules may only refer to pseudo mods that are defined before them.
+/// Pseudo mods that aren't in the mod database are still computed
+/// (so that they can be referred to) but aren't returned.
+const RULES: &[(&str, Rule)] = &[
+    // Resistances.
+    ("pseudo_total_fire_resistance", Rule::Sum(&[
+        ("#% to Fire Resistance", 1.0),
+        ("#% to Fire and Cold Resistances", 1.0),
+        ("#% to Fire and Lightning Resistances", 1.0),
+        ("#% to all Elemental Resistances", 1.0),
+    ])),
+    ("pseudo_total_cold_resistance", Rule::Sum(&[
+        ("#% to Cold Resistance", 1.0),
+        ("#% to Fire and Cold Resistances", 1.0),
+        ("#% to Cold and Lightning Resistances", 1.0),
+        ("#% to all Elemental Resistances", 1.0),
+    ])),
+    ("pseudo_total_lightning_resistance", Rule::Sum(&[
+        ("#% to Lightning Resistance", 1.0),
+        ("#% to Fire and Lightning Resistances", 1.0),
+        ("#% to Cold and Lightning Resistances", 1.0),
+        ("#% to all Elemental Resistances", 1.0),
+    ])),
+    ("pseudo_total_chaos_resistance", Rule::Sum(&[
+        ("#% to Chaos Resistance", 1.0),
+    ])),
+    ("pseudo_total_all_elemental_resistances", Rule::Sum(&[
+        ("#% to all Elemental Resistances", 1.0),
+    ])),
+    ("pseudo_total_elemental_resistance", Rule::Total(&[
+        "pseudo_total_fire_resistance",
+        "pseudo_total_cold_resistance",
+        "pseudo_total_lightning_resistance",
+    ])),
+    ("pseudo_total_resistance", Rule::Total(&[
+        "pseudo_total_elemental_resistance",
+        "pseudo_total_chaos_resistance",
+    ])),
+    ("pseudo_count_elemental_resistances", Rule::Count(&[
+        "pseudo_total_fire_resistance",
+        "pseudo_total_cold_resistance",
+        "pseudo_total_lightning_resistance",
+    ])),
+    ("pseudo_count_resistances", Rule::Count(&[
+        "pseudo_total_fire_resistance",
+        "pseudo_total_cold_resistance",
+        "pseudo_total_lightning_resistance",
+        "pseudo_total_chaos_resistance",
+    ])),
+
+    // Attributes.
+    ("pseudo_total_strength", Rule::Sum(&[
+        ("# to Strength", 1.0),
+        ("# to Strength and Dexterity", 1.0),
+        ("# to Strength and Intelligence", 1.0),
+        ("# to all Attributes", 1.0),
+    ])),
+    ("pseudo_total_dexterity", Rule::Sum(&[
+        ("# to Dexterity", 1.0),
+        ("# to Strength and Dexterity", 1.0),
+        ("# to Dexterity and Intelligence", 1.0),
+        ("# to all Attributes", 1.0),
+    ])),
+    ("pseudo_total_intelligence", Rule::Sum(&[
+        ("# to Intelligence", 1.0),
+        ("# to Strength and Intelligence", 1.0),
+        ("# to Dexterity and Intelligence", 1.0),
+        ("# to all Attributes", 1.0),
+    ])),
+    ("pseudo_total_all_attributes", Rule::Sum(&[
+        ("# to all Attributes", 1.0),
+    ])),
+
+    // Life, mana, and energy shield.
+    // Like on the trade site, these include the bonuses from Strength and Intelligence.
+    ("pseudo_total_life", Rule::Sum(&[
+        ("# to maximum Life", 1.0),
+        ("# to Strength", 0.5),
+        ("# to Strength and Dexterity", 0.5),
+        ("# to Strength and Intelligence", 0.5),
+        ("# to all Attributes", 0.5),
+    ])),
+    ("pseudo_total_mana", Rule::Sum(&[
+        ("# to maximum Mana", 1.0),
+        ("# to Intelligence", 0.5),
+        ("# to Strength and Intelligence", 0.5),
+        ("# to Dexterity and Intelligence", 0.5),
+        ("# to all Attributes", 0.5),
+    ])),
+    ("pseudo_total_energy_shield", Rule::Sum(&[
+        ("# to maximum Energy Shield", 1.0),
+    ])),
+    ("pseudo_increased_energy_shield", Rule::Sum(&[
+        ("#% increased maximum Energy Shield", 1.0),
+    ])),
+    ("pseudo_total_life_regen", Rule::Sum(&[
+        ("# Life Regenerated per second", 1.0),
+    ])),
+    ("pseudo_percent_life_regen", Rule::Sum(&[
+        ("#% of Life Regenerated per second", 1.0),
+    ])),
+    ("pseudo_increased_mana_regen", Rule::Sum(&[
+        ("#% increased Mana Regeneration Rate", 1.0),
+    ])),
+
+    // Speed.
+    ("pseudo_total_attack_speed", Rule::Sum(&[
+        ("#% increased Attack Speed", 1.0),
+        ("#% increased Attack and Cast Speed", 1.0),
+    ])),
+    ("pseudo_total_cast_speed", Rule::Sum(&[
+        ("#% increased Cast Speed", 1.0),
+        ("#% increased Attack and Cast Speed", 1.0),
+    ])),
+    ("pseudo_increased_movement_speed", Rule::Sum(&[
+        ("#% increased Movement Speed", 1.0),
+    ])),
+
+    // Damage.
+    ("pseudo_increased_spell_damage", Rule::Sum(&[
+        ("#% increased Spell Damage", 1.0),
+    ])),
+    ("pseudo_increased_elemental_damage", Rule::Sum(&[
+        ("#% increased Elemental Damage", 1.0),
+    ])),
+    ("pseudo_increased_fire_damage", Rule::Sum(&[
+        ("#% increased Fire Damage", 1.0),
+    ])),
+    ("pseudo_increased_cold_damage", Rule::Sum(&[
+        ("#% increased Cold Damage", 1.0),
+    ])),
+    ("pseudo_increased_lightning_damage", Rule::Sum(&[
+        ("#% increased Lightning Damage", 1.0),
+    ])),
+    ("pseudo_adds_physical_damage_to_attacks", Rule::Sum(&[
+        ("Adds # to # Physical Damage to Attacks", 1.0),
+    ])),
+    ("pseudo_adds_fire_damage_to_attacks", Rule::Sum(&[
+        ("Adds # to # Fire Damage to Attacks", 1.0),
+    ])),
+    ("pseudo_adds_cold_damage_to_attacks", Rule::Sum(&[
+        ("Adds # to # Cold Damage to Attacks", 1.0),
+    ])),
+    ("pseudo_adds_lightning_damage_to_attacks", Rule::Sum(&[
+        ("Adds # to # Lightning Damage to Attacks", 1.0),
+    ])),
+    ("pseudo_adds_chaos_damage_to_attacks", Rule::Sum(&[
+        ("Adds # to # Chaos Damage to Attacks", 1.0),
+    ])),
+    ("pseudo_adds_elemental_damage_to_attacks", Rule::Total(&[
+        "pseudo_adds_fire_damage_to_attacks",
+        "pseudo_adds_cold_damage_to_attacks",
+        "pseudo_adds_lightning_damage_to_attacks",
+    ])),
+    ("pseudo_adds_physical_damage_to_spells", Rule::Sum(&[
+        ("Adds # to # Physical Damage to Spells", 1.0),
+    ])),
+    ("pseudo_adds_fire_damage_to_spells", Rule::Sum(&[
+        ("Adds # to # Fire Damage to Spells", 1.0),
+    ])),
+    ("pseudo_adds_cold_damage_to_spells", Rule::Sum(&[
+        ("Adds # to # Cold Damage to Spells", 1.0),
+    ])),
+    ("pseudo_adds_lightning_damage_to_spells", Rule::Sum(&[
+        ("Adds # to # Lightning Damage to Spells", 1.0),
+    ])),
+    ("pseudo_adds_chaos_damage_to_spells", Rule::Sum(&[
+        ("Adds # to # Chaos Damage to Spells", 1.0),
+    ])),
+    ("pseudo_adds_elemental_damage_to_spells", Rule::Total(&[
+        "pseudo_adds_fire_damage_to_spells",
+        "pseudo_adds_cold_damage_to_spells",
+        "pseudo_adds_lightning_damage_to_spells",
+    ])),
+
+    // Miscellaneous.
+    ("pseudo_increased_rarity", Rule::Sum(&[
+        ("#% increased Rarity of Items found", 1.0),
+    ])),
+    ("pseudo_total_additional_gem_levels", Rule::Sum(&[
+        ("# to Level of Socketed Gems", 1.0),
+    ])),
+];
+
+
+/// Compute the pseudo mods for given (regular) item mods.
+///
+/// Only the pseudo mods with non-zero values are returned,
+/// in the order of their definitions.
+pub fn compute<'m, I>(mods: I) -> Vec<Mod>
+    where I: IntoIterator<Item=&'m Mod>
+{
+    // Sum the values of all mods with the same text template.
+    let mut values_by_template: HashMap<&str, Vec<ModValue>> = HashMap::new();
+    for mod_ in mods {
+        if mod_.mod_type() == ModType::Pseudo {
+            continue;
+        }
+        if let (Some(info), Some(values)) = (mod_.info(), mod_.values()) {
+            add_values(values_by_template.entry(info.text()).or_insert_with(Vec::new), values.iter(), 1.0);
+        }
+    }
+
+    let database = database::current();
+    let mut values_by_pseudo: HashMap<&str, Vec<ModValue>> = HashMap::new();
+    let mut result = Vec::new();
+    for &(name, ref rule) in RULES {
+        let mut values = Vec::new();
+        match *rule {
+            Rule::Sum(templates) => for &(template, factor) in templates {
+                if let Some(vs) = values_by_template.get(template) {
+                    add_values(&mut values, vs.iter().cloned(), factor);
+                }
+            },
+            Rule::Total(pseudos) => for pseudo in pseudos {
+                if let Some(vs) = values_by_pseudo.get(pseudo) {
+                    add_values(&mut values, vs.iter().cloned(), 1.0);
+                }
+            },
+            Rule::Count(pseudos) => {
+                let count = pseudos.iter()
+                    .filter(|p| values_by_pseudo.get(*p).map(|vs| is_nonzero(vs)).unwrap_or(false))
+                    .count();
+                values.push(count as ModValue);
+            }
+        }
+        if !is_nonzero(&values) {
+            continue;
+        }
+        if let Some(info) = database.lookup(&ModId::with_name(ModType::Pseudo, name)) {
+            if info.param_count() == values.len() {
+                result.push(Mod::from_info(ModType::Pseudo, info, values.iter().cloned().collect()));
+            } else {
+                warn!("Pseudo mod `{}` computed with {} values, expected {}",
+                    name, values.len(), info.param_count());
+            }
+        }
+        values_by_pseudo.insert(name, values);
+    }
+    result
+}
+
+/// Add given mod values (multiplied by `factor`) element-wise to the `sum`.
+fn add_values<I: IntoIterator<Item=ModValue>>(sum: &mut Vec<ModValue>, values: I, factor: ModValue) {
+    for (i, v) in values.into_iter().enumerate() {
+        if i < sum.len() {
+            sum[i] += v * factor;
+        } else {
+            sum.push(v * factor);
+        }
+    }
+}
+
+fn is_nonzero(values: &[ModValue]) -> bool {
+    values.iter().any(|&v| v != 0.0)
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Mod, ModType};
+    use super::compute;
+
+    #[test]
+    fn total_resistances() {
+        let mods = vec![
+            Mod::new(ModType::Explicit, "+15% to Cold Resistance"),
+            Mod::new(ModType::Explicit, "+12% to Fire and Cold Resistances"),
+            Mod::new(ModType::Implicit, "+10% to all Elemental Resistances"),
+        ];
+        let pseudo = compute(&mods);
+        let value = |text: &str| pseudo.iter()
+            .find(|m| m.info().unwrap().text() == text)
+            .map(|m| m.values().unwrap()[0]);
+
+        assert_eq!(Some(22.0), value("+#% total to Fire Resistance"));
+        assert_eq!(Some(37.0), value("+#% total to Cold Resistance"));
+        assert_eq!(Some(10.0), value("+#% total to Lightning Resistance"));
+        assert_eq!(Some(69.0), value("+#% total Elemental Resistance"));
+        assert_eq!(Some(3.0), value("# total Elemental Resistances"));
+        assert_eq!(None, value("+# total maximum Life"));
+        assert!(pseudo.iter().all(|m| m.mod_type() == ModType::Pseudo));
+    }
+
+    #[test]
+    fn total_life_includes_strength() {
+        let mods = vec![
+            Mod::new(ModType::Explicit, "+70 to maximum Life"),
+            Mod::new(ModType::Explicit, "+30 to Strength"),
+        ];
+        let pseudo = compute(&mods);
+        let life = pseudo.iter()
+            .find(|m| m.info().unwrap().text() == "+# total maximum Life")
+            .unwrap();
+        assert_eq!(85.0, life.values().unwrap()[0]);
+        assert_eq!("+85 total maximum Life", life.as_str());
+    }
+}
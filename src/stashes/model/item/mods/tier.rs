@@ -0,0 +1,405 @@
+//! Module with the data about affixes that item mods come from, and their tiers.
+//!
+//! The data itself is compiled in by the build script from `data/mods/tiers.json`,
+//! which is extracted from the game files by [RePoE](https://github.com/brather1ng/RePoE)
+//! (see the `update-tiers` recipe in the justfile).
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use stashes::model::item::{
+    AccessoryType, ArmourType, ItemCategory, JewelType, WeaponType,
+};
+use super::{Mod, ModValue, ModValues};
+use super::id::{ModId, ModType};
+
+
+lazy_static! {
+    /// All known affixes.
+    static ref AFFIXES: Vec<Arc<Affix>> = {
+        let affixes: Vec<Affix> = include!(concat!(
+            env!("OUT_DIR"), "/", "model/item/mods/tier/affixes.inc.rs"));
+        affixes.into_iter().map(Arc::new).collect()
+    };
+
+    /// Mapping of mod IDs to the affixes which can produce those mods.
+    static ref AFFIXES_BY_MOD_ID: HashMap<ModId, Vec<Arc<Affix>>> = {
+        let mut result = HashMap::new();
+        for affix in AFFIXES.iter() {
+            for stat in &affix.stats {
+                result.entry(stat.id.clone()).or_insert_with(Vec::new).push(affix.clone());
+            }
+        }
+        result
+    };
+}
+
+/// Returns all known affixes that can produce a mod with given ID.
+pub fn affixes_for(id: &ModId) -> &'static [Arc<Affix>] {
+    AFFIXES_BY_MOD_ID.get(id).map(|affixes| affixes.as_slice()).unwrap_or(&[])
+}
+
+/// Returns the names of all known affixes of given type.
+pub fn affix_names(affix_type: AffixType) -> Box<Iterator<Item=&'static str>> {
+    Box::new(AFFIXES.iter().filter(move |a| a.type_ == affix_type).map(|a| a.name.as_str()))
+}
+
+/// Returns all known affixes of given type and name (e.g. "of the Titan").
+///
+/// There can be more than one, as the same name is sometimes shared
+/// by affixes which roll on different kinds of items.
+pub fn affixes_named(affix_type: AffixType, name: &str) -> Vec<&'static Affix> {
+    AFFIXES.iter()
+        .filter(|a| a.type_ == affix_type && a.name == name)
+        .map(|a| &**a)
+        .collect()
+}
+
+
+/// Generation type of an affix.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum AffixType {
+    /// Prefix, whose name is displayed before the item base name (on magic items).
+    Prefix,
+    /// Suffix, whose name is displayed after the item base name (on magic items).
+    Suffix,
+}
+
+impl fmt::Display for AffixType {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AffixType::Prefix => write!(fmt, "prefix"),
+            AffixType::Suffix => write!(fmt, "suffix"),
+        }
+    }
+}
+
+
+/// An affix that can roll on items and give them one or more mods.
+///
+/// Affixes that give more than one mod are colloquially called *hybrid* affixes.
+#[derive(Clone, Debug)]
+pub struct Affix {
+    /// Internal identifier of the affix, e.g. "IncreasedLife7".
+    id: String,
+    /// Name of the affix, e.g. "Virile" or "of the Titan".
+    name: String,
+    /// Whether the affix is a prefix or a suffix.
+    type_: AffixType,
+    /// Minimum item level required for the affix to roll.
+    level: u64,
+    /// Mod stats that the affix gives, along with their value ranges.
+    stats: Vec<AffixStat>,
+    /// Spawn weights of the affix for item tags, in order of precedence.
+    weights: Vec<(String, u32)>,
+}
+
+/// A single mod stat given by an `Affix`.
+#[derive(Clone, Debug)]
+pub struct AffixStat {
+    /// ID of the mod.
+    id: ModId,
+    /// Inclusive ranges of the possible mod values.
+    ranges: Vec<(ModValue, ModValue)>,
+}
+
+impl Affix {
+    /// Create `Affix` from its parts (usually in the code generated by the build script).
+    pub(super) fn precompiled<I, N>(id: I, name: N, type_: AffixType, level: u64,
+                                    stats: Vec<AffixStat>, weights: Vec<(&str, u32)>) -> Self
+        where I: Into<String>, N: Into<String>
+    {
+        Affix {
+            id: id.into(),
+            name: name.into(),
+            type_, level, stats,
+            weights: weights.into_iter().map(|(tag, w)| (tag.to_owned(), w)).collect(),
+        }
+    }
+}
+
+impl AffixStat {
+    /// Create `AffixStat` for given mod ID and value ranges.
+    pub(super) fn new(id: ModId, ranges: Vec<(ModValue, ModValue)>) -> Self {
+        AffixStat{id, ranges}
+    }
+}
+
+impl Affix {
+    /// Internal identifier of the affix, e.g. "IncreasedLife7".
+    #[inline]
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    /// Name of the affix, e.g. "Virile" or "of the Titan".
+    ///
+    /// This is what shows up in the names of magic items.
+    #[inline]
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Whether the affix is a prefix or a suffix.
+    #[inline]
+    pub fn affix_type(&self) -> AffixType {
+        self.type_
+    }
+
+    /// Minimum item level required for the affix to roll.
+    #[inline]
+    pub fn required_level(&self) -> u64 {
+        self.level
+    }
+
+    /// Mod stats that the affix gives.
+    #[inline]
+    pub fn stats(&self) -> &[AffixStat] {
+        self.stats.as_slice()
+    }
+
+    /// Whether this is a hybrid affix, i.e. one that gives more than one mod.
+    #[inline]
+    pub fn is_hybrid(&self) -> bool {
+        self.stats.len() > 1
+    }
+
+    /// Spawn weight of the affix for items of given category.
+    ///
+    /// Zero means the affix doesn't roll on such items at all.
+    pub fn weight(&self, category: &ItemCategory) -> u32 {
+        let tags = item_tags(category);
+        self.weights.iter()
+            .find(|&&(ref tag, _)| tag == "default" || tags.contains(&tag.as_str()))
+            .map(|&(_, w)| w)
+            .unwrap_or(0)
+    }
+
+    /// Whether the affix can roll on an item of given category and item level.
+    #[inline]
+    pub fn can_roll_on(&self, category: &ItemCategory, item_level: u64) -> bool {
+        self.level <= item_level && self.weight(category) > 0
+    }
+
+    /// The stat of the affix that corresponds to given mod ID, if any.
+    fn stat(&self, id: &ModId) -> Option<&AffixStat> {
+        self.stats.iter().find(|s| &s.id == id)
+    }
+
+    /// Whether the affix gives exactly the same mod stats as the other one.
+    ///
+    /// Items cannot have two such affixes at the same time, even if they're of different tiers.
+    pub fn has_same_stats(&self, other: &Affix) -> bool {
+        self.stats.len() == other.stats.len()
+            && self.stats.iter().zip(&other.stats).all(|(a, b)| a.id == b.id)
+    }
+}
+
+impl AffixStat {
+    /// ID of the mod that the stat corresponds to.
+    #[inline]
+    pub fn mod_id(&self) -> &ModId {
+        &self.id
+    }
+
+    /// Inclusive ranges of values that the mod can roll with,
+    /// one for every mod value.
+    #[inline]
+    pub fn ranges(&self) -> &[(ModValue, ModValue)] {
+        self.ranges.as_slice()
+    }
+
+    /// Whether given mod values fit within the ranges of this stat.
+    pub fn contains(&self, values: &ModValues) -> bool {
+        values.len() == self.ranges.len()
+            && values.iter().zip(&self.ranges).all(|(v, &(min, max))| min <= v && v <= max)
+    }
+}
+
+
+/// Tier of a mod on an item, i.e. the affix it most likely came from
+/// and how that affix compares to others giving the same stats.
+#[derive(Clone, Debug)]
+pub struct ModTier {
+    /// The affix that the mod came from.
+    affix: Arc<Affix>,
+    /// Tier number, where 1 is the best tier.
+    number: usize,
+    /// Total number of tiers for the item category and item level.
+    count: usize,
+    /// How close the values are to the maximum of the tier's ranges.
+    roll: f64,
+}
+
+impl ModTier {
+    /// Affix that the mod came from.
+    #[inline]
+    pub fn affix(&self) -> &Affix {
+        &*self.affix
+    }
+
+    /// Whether the mod came from a prefix or a suffix.
+    #[inline]
+    pub fn affix_type(&self) -> AffixType {
+        self.affix.type_
+    }
+
+    /// Tier number, where 1 is the best (highest level) tier.
+    #[inline]
+    pub fn number(&self) -> usize {
+        self.number
+    }
+
+    /// Total number of tiers the mod has for the item category and item level.
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// How close the roll is to the maximum of the tier's value ranges.
+    ///
+    /// This is a number from 0.0 (the tier's minimum) to 1.0 (its maximum).
+    /// For mods with multiple values (e.g. "Adds # to # Fire Damage"),
+    /// the result is averaged.
+    #[inline]
+    pub fn roll(&self) -> f64 {
+        self.roll
+    }
+}
+
+impl Mod {
+    /// Determine the tier of the mod on an item of given category and item level.
+    ///
+    /// Only the affixes which can roll at that item level are considered,
+    /// so the tier number is relative to the best tier available to the item.
+    ///
+    /// Returns `None` if the mod hasn't been resolved against the mod database,
+    /// or there is no tier data about it.
+    pub fn tier(&self, category: &ItemCategory, item_level: u64) -> Option<ModTier> {
+        let (ref info, ref values) = *self.data.as_ref()?;
+        let id = info.id();
+
+        // Prefer regular affixes to hybrid ones which also happen to fit the values.
+        let candidates: Vec<&Arc<Affix>> = affixes_for(id).iter()
+            .filter(|a| a.can_roll_on(category, item_level))
+            .collect();
+        let affix = *candidates.iter()
+            .filter(|a| a.stat(id).map(|s| s.contains(values)).unwrap_or(false))
+            .min_by_key(|a| a.stats.len())?;
+
+        let mut tiers: Vec<&Arc<Affix>> = candidates.into_iter()
+            .filter(|a| a.has_same_stats(affix))
+            .collect();
+        tiers.sort_by(|a, b| b.level.cmp(&a.level));
+        let number = tiers.iter().position(|a| Arc::ptr_eq(a, affix)).unwrap() + 1;
+
+        let ranges = &affix.stat(id).unwrap().ranges;
+        let roll = values.iter().zip(ranges)
+            .map(|(v, &(min, max))| if max > min { (v - min) / (max - min) } else { 1.0 })
+            .sum::<f64>() / ranges.len().max(1) as f64;
+
+        Some(ModTier{affix: affix.clone(), number, count: tiers.len(), roll})
+    }
+}
+
+
+/// Tags of items of given category, used to determine the spawn weights of affixes.
+fn item_tags(category: &ItemCategory) -> &'static [&'static str] {
+    match *category {
+        ItemCategory::Accessory(AccessoryType::Amulet) => &["amulet"],
+        ItemCategory::Accessory(AccessoryType::Belt) => &["belt"],
+        ItemCategory::Accessory(AccessoryType::Ring) => &["ring"],
+        ItemCategory::Armour(ArmourType::Helmet) => &["helmet", "armour"],
+        ItemCategory::Armour(ArmourType::Gloves) => &["gloves", "armour"],
+        ItemCategory::Armour(ArmourType::Chest) => &["body_armour", "armour"],
+        ItemCategory::Armour(ArmourType::Boots) => &["boots", "armour"],
+        ItemCategory::Armour(ArmourType::Shield) => &["shield", "armour"],
+        ItemCategory::Armour(ArmourType::Quiver) => &["quiver"],
+        ItemCategory::Weapon(WeaponType::Bow) => &["bow", "two_hand_weapon", "weapon"],
+        ItemCategory::Weapon(WeaponType::Claw) => &["claw", "one_hand_weapon", "weapon"],
+        ItemCategory::Weapon(WeaponType::Dagger) => &["dagger", "one_hand_weapon", "weapon"],
+        ItemCategory::Weapon(WeaponType::OneHandedAxe) => &["axe", "one_hand_weapon", "weapon"],
+        ItemCategory::Weapon(WeaponType::OneHandedMace) => &["mace", "one_hand_weapon", "weapon"],
+        ItemCategory::Weapon(WeaponType::OneHandedSword) => &["sword", "one_hand_weapon", "weapon"],
+        ItemCategory::Weapon(WeaponType::Sceptre) => &["sceptre", "one_hand_weapon", "weapon"],
+        ItemCategory::Weapon(WeaponType::Staff) => &["staff", "two_hand_weapon", "weapon"],
+        ItemCategory::Weapon(WeaponType::TwoHandedAxe) => &["axe", "two_hand_weapon", "weapon"],
+        ItemCategory::Weapon(WeaponType::TwoHandedMace) => &["mace", "two_hand_weapon", "weapon"],
+        ItemCategory::Weapon(WeaponType::TwoHandedSword) => &["sword", "two_hand_weapon", "weapon"],
+        ItemCategory::Weapon(WeaponType::Wand) => &["wand", "one_hand_weapon", "weapon"],
+        ItemCategory::Jewel(JewelType::Regular) => &["jewel"],
+        ItemCategory::Jewel(JewelType::Abyss) => &["abyss_jewel"],
+        _ => &[],
+    }
+}
+
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use stashes::model::item::{AccessoryType, ArmourType, ItemCategory, WeaponType};
+    use super::super::{Mod, ModType};
+    use super::AffixType;
+
+    const RING: ItemCategory = ItemCategory::Accessory(AccessoryType::Ring);
+    const CHEST: ItemCategory = ItemCategory::Armour(ArmourType::Chest);
+    const BOW: ItemCategory = ItemCategory::Weapon(WeaponType::Bow);
+    const MAX_ILVL: u64 = 100;
+
+    #[test]
+    fn tier__top() {
+        let mod_ = Mod::new(ModType::Explicit, "+125 to maximum Life");
+        let tier = mod_.tier(&CHEST, MAX_ILVL).unwrap();
+        assert_eq!(AffixType::Prefix, tier.affix_type());
+        assert_eq!(1, tier.number());
+        assert!(tier.count() > 1);
+        assert!(0.5 < tier.roll() && tier.roll() < 0.6);
+    }
+
+    #[test]
+    fn tier__depends_on_category() {
+        let mod_ = Mod::new(ModType::Explicit, "+65 to maximum Life");
+        let chest_tier = mod_.tier(&CHEST, MAX_ILVL).unwrap();
+        let ring_tier = mod_.tier(&RING, MAX_ILVL).unwrap();
+        assert_eq!(chest_tier.affix().id(), ring_tier.affix().id());
+        assert!(chest_tier.number() > ring_tier.number());
+        assert!(Mod::new(ModType::Explicit, "+125 to maximum Life").tier(&RING, MAX_ILVL).is_none());
+    }
+
+    #[test]
+    fn tier__depends_on_item_level() {
+        let mod_ = Mod::new(ModType::Explicit, "+65 to maximum Life");
+        let high_tier = mod_.tier(&CHEST, MAX_ILVL).unwrap();
+        let low_tier = mod_.tier(&CHEST, 40).unwrap();
+        assert_eq!(high_tier.affix().id(), low_tier.affix().id());
+        assert_eq!(1, low_tier.number());
+        assert!(high_tier.number() > low_tier.number());
+        assert!(high_tier.count() > low_tier.count());
+        assert!(mod_.tier(&CHEST, 20).is_none());
+    }
+
+    #[test]
+    fn tier__multiple_values() {
+        let mod_ = Mod::new(ModType::Explicit, "Adds 1 to 4 Fire Damage to Attacks");
+        let tier = mod_.tier(&RING, MAX_ILVL).unwrap();
+        assert_eq!(tier.count(), tier.number());
+        assert_eq!(0.5, tier.roll());
+    }
+
+    #[test]
+    fn tier__local_stat() {
+        // Local mods have the same text on items as their global counterparts.
+        let mod_ = Mod::new(ModType::Explicit, "12% increased Attack Speed");
+        let tier = mod_.tier(&BOW, MAX_ILVL).unwrap();
+        assert!(tier.affix().id().starts_with("LocalIncreasedAttackSpeed"));
+        assert_eq!(AffixType::Suffix, tier.affix_type());
+        assert!(mod_.tier(&RING, MAX_ILVL).is_none());
+    }
+
+    #[test]
+    fn tier__unknown() {
+        let mod_ = Mod::new(ModType::Explicit, "Has 1 Socket");
+        assert!(mod_.tier(&RING, MAX_ILVL).is_none());
+    }
+}
@@ -0,0 +1,72 @@
+//! Tokenizer of mod texts and their templates.
+//!
+//! This module is also compiled into the build script (which precompiles
+//! the lookup keys of bundled mods), so it mustn't depend on anything but `std`.
+
+/// Number occurring in mod text or its template.
+#[derive(Debug)]
+pub(super) struct Number {
+    /// Digits of the number, or `None` for value placeholders in templates.
+    pub(super) digits: Option<String>,
+    /// How many plus signs are preceding the number.
+    pub(super) pluses: usize,
+}
+
+/// Split mod text (or its template, if `is_template` is true) into its lookup key
+/// and the numbers it contains.
+pub(super) fn tokenize(text: &str, is_template: bool) -> (String, Vec<Number>) {
+    // Mod values in templates may have an additional plus sign before them,
+    // so actual mod texts can have two.
+    let max_pluses = if is_template { 1 } else { 2 };
+    let chars: Vec<char> = text.chars().collect();
+    let is_number_start = |i: usize| chars.get(i).map(|c| {
+        c.is_ascii_digit() || (is_template && *c == '#')
+    }).unwrap_or(false);
+
+    let mut key = String::with_capacity(text.len());
+    let mut numbers = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let mut pluses = 0;
+        while pluses < max_pluses && chars.get(i + pluses) == Some(&'+') {
+            pluses += 1;
+        }
+        if pluses > 0 && is_number_start(i + pluses) {
+            i += pluses;
+        } else {
+            pluses = 0;
+        }
+
+        if is_template && chars[i] == '#' {
+            numbers.push(Number{digits: None, pluses});
+            key.push('#');
+            i += 1;
+        } else if chars[i].is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            if chars.get(i) == Some(&'.') && chars.get(i + 1).map(|c| c.is_ascii_digit()).unwrap_or(false) {
+                i += 1;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            numbers.push(Number{digits: Some(chars[start..i].iter().collect()), pluses});
+            key.push('#');
+        } else {
+            // Hashes in actual mod texts (as opposed to templates) are not placeholders,
+            // so they mustn't be confused with them in the key.
+            // Line breaks in multi-line mods are equivalent to spaces,
+            // as the API isn't consistent about where (or whether) it puts them.
+            match chars[i] {
+                '#' => key.push('\u{fffd}'),
+                '\n' => key.push(' '),
+                '\r' => {}
+                c => key.extend(c.to_lowercase()),
+            }
+            i += 1;
+        }
+    }
+    (key, numbers)
+}
@@ -10,6 +10,8 @@ use std::fmt;
 use std::hash::Hash;
 use std::iter::FromIterator;
 
+use itertools::Itertools;
+
 use ::common::util::ExplicitDebug;
 
 
@@ -19,8 +21,10 @@ pub type Key = String;
 
 
 /// Type of a property value.
+///
+/// Some properties have multiple values (e.g. flask charges per use & max charges,
+/// or elemental damage of several types); see `Properties::get_values`.
 pub type Value = String;
-// TODO: support multi-value properties (for damage ranges and for flask charges (per-use & max))
 // TODO: introduce an enum here that corresponds to the valueType enum from the API
 // (mostly to hold the types of elemental damage, and whether or not it was affected by an affix)
 
@@ -33,7 +37,8 @@ pub type Value = String;
 #[derive(Default)]
 pub struct Properties {
     set: HashSet<Key>,
-    map: HashMap<Key, Value>,
+    /// Properties with values. Each of the `Vec`s is non-empty.
+    map: HashMap<Key, Vec<Value>>,
     // Marker to ensure the type doesn't get the `Sync` trait derived
     // because some mutation methods cannot be thread-safe w/o locks.
     _marker: UnsafeCell<()>,
@@ -59,11 +64,21 @@ impl Properties {
     }
 
     /// Retrieve the `Value` of given property, if it exists and has one.
+    ///
+    /// For properties with multiple values, this is the first one.
     #[inline]
     pub fn get_value<'p, K: ?Sized>(&'p self, k: &K) -> Option<&'p Value>
         where Key: Borrow<K>, K: Hash + Eq
     {
-        self.map.get(k)
+        self.map.get(k).map(|vs| &vs[0])
+    }
+
+    /// Retrieve all the `Value`s of given property, if it exists and has any.
+    #[inline]
+    pub fn get_values<'p, K: ?Sized>(&'p self, k: &K) -> Option<&'p [Value]>
+        where Key: Borrow<K>, K: Hash + Eq
+    {
+        self.map.get(k).map(|vs| vs.as_slice())
     }
 
     /// Checks whether the properties container is empty.
@@ -73,10 +88,12 @@ impl Properties {
     }
 
     /// Return an iterator over property keys & optional values as pairs.
+    ///
+    /// For properties with multiple values, only the first one is yielded.
     #[inline]
     pub fn iter<'p>(&'p self) -> impl Iterator<Item=(&'p Key, Option<&'p Value>)> + 'p {
         self.set.iter().map(|p| (p, None))
-            .chain(self.map.iter().map(|(k, v)| (k, Some(v))))
+            .chain(self.map.iter().map(|(k, vs)| (k, Some(&vs[0]))))
     }
 
     /// Return an iterator over property keys (names).
@@ -124,7 +141,7 @@ impl Properties {
     /// but only modify the value if they already have one.
     pub fn iter_mut<'p>(&'p mut self) -> impl Iterator<Item=(&'p Key, Option<&'p mut Value>)> + 'p {
         self.set.iter().map(|k| (k, None))
-                .chain(self.map.iter_mut().map(|(k, v)| (k, Some(v)))
+                .chain(self.map.iter_mut().map(|(k, vs)| (k, Some(&mut vs[0])))
         )
     }
     // TODO: an Entry-like API to insert properties and add/remove their values
@@ -137,7 +154,7 @@ impl Properties {
     ///
     /// If the property didn't exist already, `None` is returned.
     pub fn put(&mut self, key: Key) -> Option<Option<Value>> {
-        let prev = self.map.remove(&key);
+        let prev = self.map.remove(&key).map(first_value);
         // There is a brief window between the above and below calls where the container
         // is in transitional state that shouldn't be observed,
         // which is why it is made thread-unsafe (i.e. not `Sync`).
@@ -150,10 +167,24 @@ impl Properties {
     /// if any, is returned. This means that in case the property existed before
     /// but didn't have a value, the function will return `Some(None)`.
     pub fn put_with_value(&mut self, key: Key, value: Value) -> Option<Option<Value>> {
+        self.put_with_values(key, vec![value])
+    }
+
+    /// Insert a new property with given values into the container.
+    ///
+    /// If `values` is empty, the property will be inserted without any value
+    /// (just like with `put`).
+    ///
+    /// The return value has the same meaning as in `put_with_value`,
+    /// i.e. it only includes the first of the previous values.
+    pub fn put_with_values(&mut self, key: Key, values: Vec<Value>) -> Option<Option<Value>> {
+        if values.is_empty() {
+            return self.put(key);
+        }
         let was_in_set = self.set.remove(&key);
         //  The same note about brief transitional state applies here.
-        match self.map.insert(key, value) {
-            Some(prev) => Some(Some(prev)),
+        match self.map.insert(key, values) {
+            Some(prev) => Some(Some(first_value(prev))),
             None => if was_in_set { Some(None) } else { None },
         }
     }
@@ -167,15 +198,32 @@ impl Properties {
     /// in which case the return value will be `Some(None)`.
     pub fn remove<K: ?Sized>(&mut self, key: &K) -> Option<Option<Value>>
         where Key: Borrow<K>, K: Hash + Eq
+    {
+        self.remove_values(key).map(|vs| vs.into_iter().next())
+    }
+
+    /// Remove a property from the container
+    /// and return all the values associated with it.
+    ///
+    /// If the property didn't exist, this method will return `None`.
+    /// If it existed but didn't have any values, the result is an empty `Vec`.
+    pub fn remove_values<K: ?Sized>(&mut self, key: &K) -> Option<Vec<Value>>
+        where Key: Borrow<K>, K: Hash + Eq
     {
         if self.set.remove(key) {
-            Some(None)
+            Some(vec![])
         } else {
-            self.map.remove(key).map(Some)
+            self.map.remove(key)
         }
     }
 }
 
+/// Take the first of property values, discarding the rest.
+#[inline]
+fn first_value(values: Vec<Value>) -> Value {
+    values.into_iter().next().expect("non-empty property values")
+}
+
 impl FromIterator<Key> for Properties {
     fn from_iter<T: IntoIterator<Item=Key>>(iter: T) -> Self {
         Properties{set: iter.into_iter().collect(), ..Properties::default()}
@@ -183,7 +231,10 @@ impl FromIterator<Key> for Properties {
 }
 impl FromIterator<(Key, Value)> for Properties {
     fn from_iter<T: IntoIterator<Item=(Key, Value)>>(iter: T) -> Self {
-        Properties{map: iter.into_iter().collect(), ..Properties::default()}
+        Properties{
+            map: iter.into_iter().map(|(k, v)| (k, vec![v])).collect(),
+            ..Properties::default()
+        }
     }
 }
 impl FromIterator<(Key, Option<Value>)> for Properties {
@@ -191,7 +242,7 @@ impl FromIterator<(Key, Option<Value>)> for Properties {
         let (map_items, set_items): (Vec<_>, Vec<_>) =
             iter.into_iter().partition(|&(_, ref v)| v.is_some());
         Properties {
-            map: map_items.into_iter().map(|(k, v)| (k, v.unwrap())).collect(),
+            map: map_items.into_iter().map(|(k, v)| (k, vec![v.unwrap()])).collect(),
             set: set_items.into_iter().map(|(k, _)| k).collect(),
             ..Properties::default()
         }
@@ -204,7 +255,7 @@ impl IntoIterator for Properties {
     fn into_iter(self) -> Self::IntoIter {
         Box::new(
             self.set.into_iter().map(|p| (p, None))
-                .chain(self.map.into_iter().map(|(k, v)| (k, Some(v))))
+                .chain(self.map.into_iter().map(|(k, vs)| (k, Some(first_value(vs)))))
         )
     }
 }
@@ -220,11 +271,12 @@ impl fmt::Debug for Properties {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         fmt.debug_set()
             .entries(
-                self.iter().map(|(k, v)| {
-                    format!("\"{}\"{}", k, v.map(|v| format!(": \"{}\"", v))
-                        .unwrap_or_else(String::new))
-                })
-                .map(ExplicitDebug::from))
+                self.set.iter().map(|k| format!("\"{}\"", k))
+                    .chain(self.map.iter().map(|(k, vs)| {
+                        format!("\"{}\": {}", k,
+                            vs.iter().format_with(", ", |v, f| f(&format_args!("\"{}\"", v))))
+                    }))
+                    .map(ExplicitDebug::from))
             .finish()
     }
 }
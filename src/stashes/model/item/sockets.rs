@@ -1,5 +1,12 @@
 //! Structures defining the sockets on items.
 
+use std::error;
+use std::fmt;
+use std::mem;
+use std::str::FromStr;
+
+use super::Item;
+
 
 /// A color of an gem or socket.
 ///
@@ -25,22 +32,63 @@ pub enum Color {
     White,
 }
 
+impl Color {
+    /// Color with given letter (as used in the socket notation, e.g. "R-G-B").
+    pub fn from_letter(letter: char) -> Option<Color> {
+        match letter.to_ascii_uppercase() {
+            'R' => Some(Color::Red),
+            'G' => Some(Color::Green),
+            'B' => Some(Color::Blue),
+            'W' => Some(Color::White),
+            _ => None,
+        }
+    }
+
+    /// Letter used for this color in the socket notation (e.g. "R-G-B").
+    pub fn letter(&self) -> char {
+        match *self {
+            Color::Red => 'R',
+            Color::Green => 'G',
+            Color::Blue => 'B',
+            Color::White => 'W',
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}", self.letter())
+    }
+}
+
+/// All the socket colors.
+const ALL_COLORS: &[Color] = &[Color::Red, Color::Green, Color::Blue, Color::White];
+
+/// Letter used for abyssal sockets in the socket notation.
+const ABYSSAL_LETTER: char = 'A';
+
 
 /// Sockets an item has, if any.
 #[derive(Debug)]
 pub struct ItemSockets {
-    /// Number of abyss sockets the item has.
-    pub(crate) abyssal_count: u64,
+    /// Items socketed into abyssal sockets, one entry per abyssal socket
+    /// (`None` for empty sockets).
+    pub(crate) abyssal_items: Vec<Option<Item>>,
     /// Groups of regular sockets that are linked together.
     pub(crate) regular_groups: Vec<SocketGroup>,
+    /// Positions of all sockets in the order they are listed by the API.
+    ///
+    /// This is what the socket indices (e.g. in `SocketedItem::socket`) refer to.
+    pub(crate) positions: Vec<SocketPosition>,
 }
 
 impl Default for ItemSockets {
     fn default() -> Self {
         // Default is no sockets at all, for items that don't have them at all.
         ItemSockets {
-            abyssal_count: 0,
+            abyssal_items: vec![],
             regular_groups: vec![],
+            positions: vec![],
         }
     }
 }
@@ -54,7 +102,13 @@ impl ItemSockets {
     /// Number of abyssal sockets this item has.
     #[inline]
     pub fn abyssal_count(&self) -> u64 {
-        self.abyssal_count
+        self.abyssal_items.len() as u64
+    }
+
+    /// Total number of sockets (regular & abyssal) this item has.
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.positions.len()
     }
 
     /// Colors of all regular sockets (in an unspecified order).
@@ -63,11 +117,30 @@ impl ItemSockets {
         self.regular_groups.iter().flat_map(|g| g.colors.iter().cloned())
     }
 
+    /// How many regular sockets of a particular color are there on the item
+    /// (regardless of links).
+    #[inline]
+    pub fn count_of(&self, color: Color) -> usize {
+        self.regular_groups.iter().map(|g| g.count_of(color)).sum()
+    }
+
     /// Linked groups of regular sockets.
     pub fn links<'s>(&'s self) -> impl Iterator<Item=impl Iterator<Item=Color> + 's> + 's {
         self.regular_groups.iter().map(|g| g.colors.iter().cloned())
     }
 
+    /// Linked groups of regular sockets, as `SocketGroup` objects.
+    #[inline]
+    pub fn groups<'s>(&'s self) -> impl Iterator<Item=&'s SocketGroup> + 's {
+        self.regular_groups.iter()
+    }
+
+    /// Position of the socket with given index, if it exists.
+    #[inline]
+    pub fn position(&self, socket: usize) -> Option<SocketPosition> {
+        self.positions.get(socket).cloned()
+    }
+
     /// Maximum number of linked sockets on the item.
     ///
     /// If an item is said to be N-linked (e.g. 5-linked), this will be N.
@@ -75,6 +148,179 @@ impl ItemSockets {
     pub fn max_links(&self) -> usize {
         self.regular_groups.iter().map(|g| g.size()).max().unwrap_or(0)
     }
+
+    /// Whether the item has a linked socket group with at least the given socket colors,
+    /// e.g. `&[Red, Red, Green]` for "at least 2R and 1G linked".
+    ///
+    /// White sockets only satisfy `White` in `colors` here;
+    /// see `can_hold` for treating them as wildcards.
+    pub fn has_linked(&self, colors: &[Color]) -> bool {
+        self.regular_groups.iter().any(|g| g.has_at_least(colors))
+    }
+
+    /// Whether the item can hold a linked set of gems with given colors.
+    ///
+    /// Unlike in `has_linked`, white sockets can hold gems of any color,
+    /// and white gems can be put in sockets of any color.
+    pub fn can_hold(&self, gem_colors: &[Color]) -> bool {
+        self.regular_groups.iter().any(|g| g.can_hold(gem_colors))
+    }
+}
+
+impl FromStr for ItemSockets {
+    type Err = ParseSocketsError;
+
+    /// Parse the sockets from the common textual notation, e.g. "R-R-G B W".
+    ///
+    /// Linked sockets are separated by dashes, while unlinked socket groups
+    /// are separated by whitespace. Abyssal sockets are denoted by "A".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut result = ItemSockets::default();
+        for group in s.split_whitespace() {
+            let letters: Vec<_> = group.split('-').collect();
+            let is_abyssal = |l: &&str| l.to_uppercase() == ABYSSAL_LETTER.to_string();
+            if letters.iter().any(is_abyssal) {
+                if letters.len() > 1 {
+                    return Err(ParseSocketsError::LinkedAbyssal(group.to_owned()));
+                }
+                result.positions.push(SocketPosition::Abyssal(result.abyssal_items.len()));
+                result.abyssal_items.push(None);
+                continue;
+            }
+
+            let id = result.regular_groups.len() as u8;
+            let mut colors = Vec::with_capacity(letters.len());
+            for letter in letters {
+                let mut chars = letter.chars();
+                let color = match (chars.next(), chars.next()) {
+                    (Some(c), None) => Color::from_letter(c),
+                    _ => None,
+                }.ok_or_else(|| ParseSocketsError::InvalidColor(letter.to_owned()))?;
+                result.positions.push(SocketPosition::Regular{group: id, index: colors.len()});
+                colors.push(color);
+            }
+            result.regular_groups.push(SocketGroup::new(id, colors));
+        }
+        Ok(result)
+    }
+}
+
+impl fmt::Display for ItemSockets {
+    /// Format the sockets in the common textual notation, e.g. "R-R-G B W".
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let mut prev_group = None;
+        for (i, &position) in self.positions.iter().enumerate() {
+            let (group, letter) = match position {
+                SocketPosition::Regular{group, index} =>
+                    (Some(group), self.regular_groups[group as usize].colors[index].letter()),
+                SocketPosition::Abyssal(_) => (None, ABYSSAL_LETTER),
+            };
+            if i > 0 {
+                let linked = group.is_some() && group == prev_group;
+                write!(fmt, "{}", if linked { "-" } else { " " })?;
+            }
+            write!(fmt, "{}", letter)?;
+            prev_group = group;
+        }
+        Ok(())
+    }
+}
+
+/// Error when parsing `ItemSockets` from string.
+#[derive(Debug)]
+pub enum ParseSocketsError {
+    /// Error for when a socket color letter isn't recognized.
+    InvalidColor(String),
+    /// Error for when an abyssal socket is linked with other sockets.
+    LinkedAbyssal(String),
+}
+impl error::Error for ParseSocketsError {
+    fn description(&self) -> &str { "error parsing item sockets" }
+    fn cause(&self) -> Option<&error::Error> { None }
+}
+impl fmt::Display for ParseSocketsError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseSocketsError::InvalidColor(ref s) => write!(fmt, "invalid socket color `{}`", s),
+            ParseSocketsError::LinkedAbyssal(ref s) =>
+                write!(fmt, "abyssal socket cannot be linked: `{}`", s),
+        }
+    }
+}
+
+// Socketed items.
+impl ItemSockets {
+    /// Items (gems and abyss jewels) that are socketed into the sockets of this item,
+    /// in the order of sockets.
+    pub fn socketed_items<'s>(&'s self) -> impl Iterator<Item=SocketedItem<'s>> + 's {
+        self.positions.iter().enumerate().filter_map(move |(socket, &position)| {
+            self.item_at(position).map(|item| SocketedItem{socket, position, item})
+        })
+    }
+
+    /// Items (abyss jewels) socketed into the abyssal sockets of this item.
+    #[inline]
+    pub fn abyssal_items<'s>(&'s self) -> impl Iterator<Item=&'s Item> + 's {
+        self.abyssal_items.iter().filter_map(|i| i.as_ref())
+    }
+
+    /// Whether any items are socketed into this item.
+    #[inline]
+    pub fn has_socketed_items(&self) -> bool {
+        self.socketed_items().next().is_some()
+    }
+
+    /// Item socketed at given position, if any.
+    pub fn item_at(&self, position: SocketPosition) -> Option<&Item> {
+        match position {
+            SocketPosition::Regular{group, index} => self.regular_groups.get(group as usize)
+                .and_then(|g| g.items.get(index)).and_then(|i| i.as_ref()),
+            SocketPosition::Abyssal(index) =>
+                self.abyssal_items.get(index).and_then(|i| i.as_ref()),
+        }
+    }
+
+    /// Put an item into the socket of given index.
+    ///
+    /// Returns the item that has been previously socketed there (if any),
+    /// or `Err` with the item if there is no socket with such index.
+    pub(crate) fn put_item(&mut self, socket: usize, item: Item) -> Result<Option<Item>, Item> {
+        let slot = match self.positions.get(socket) {
+            Some(&SocketPosition::Regular{group, index}) => self.regular_groups
+                .get_mut(group as usize).and_then(|g| g.items.get_mut(index)),
+            Some(&SocketPosition::Abyssal(index)) => self.abyssal_items.get_mut(index),
+            None => None,
+        };
+        match slot {
+            Some(slot) => Ok(mem::replace(slot, Some(item))),
+            None => Err(item),
+        }
+    }
+}
+
+/// Position of a particular socket within the item.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SocketPosition {
+    /// Regular socket in one of the linked `SocketGroup`s.
+    Regular {
+        /// ID of the socket group.
+        group: u8,
+        /// Index of the socket within the group.
+        index: usize,
+    },
+    /// Abyssal socket with given index (among all abyssal sockets of the item).
+    Abyssal(usize),
+}
+
+/// An item (a gem or an abyss jewel) that's socketed into another item.
+#[derive(Clone, Copy, Debug)]
+pub struct SocketedItem<'s> {
+    /// Index of the socket among all sockets of the item.
+    pub socket: usize,
+    /// Position of the socket, including its linked group for regular sockets.
+    pub position: SocketPosition,
+    /// The socketed item itself.
+    pub item: &'s Item,
 }
 
 /// A group of linked sockets on an item.
@@ -86,9 +332,35 @@ pub struct SocketGroup {
     pub(crate) id: u8,
     /// Colors of linked sockets.
     pub(crate) colors: Vec<Color>,
+    /// Items socketed in the linked sockets (`None` for empty sockets).
+    ///
+    /// This always has the same length as `colors`.
+    pub(crate) items: Vec<Option<Item>>,
+}
+
+impl SocketGroup {
+    /// Create a `SocketGroup` of empty sockets with given colors.
+    pub(crate) fn new(id: u8, colors: Vec<Color>) -> Self {
+        let items = colors.iter().map(|_| None).collect();
+        SocketGroup{id, colors, items}
+    }
 }
 
 impl SocketGroup {
+    /// ID of the socket group.
+    ///
+    /// This is a small integer index starting from 0.
+    #[inline]
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    /// Colors of the linked sockets, in the order they appear on the item.
+    #[inline]
+    pub fn colors<'g>(&'g self) -> impl Iterator<Item=Color> + 'g {
+        self.colors.iter().cloned()
+    }
+
     /// Size of the socket group (number of sockets therein).
     #[inline]
     pub fn size(&self) -> usize {
@@ -100,4 +372,104 @@ impl SocketGroup {
     pub fn count_of(&self, color: Color) -> usize {
         self.colors.iter().filter(|&&c| c == color).count()
     }
+
+    /// Whether the group has at least the given socket colors
+    /// (treating white sockets as just another color).
+    pub fn has_at_least(&self, colors: &[Color]) -> bool {
+        ALL_COLORS.iter().all(|&c| {
+            self.count_of(c) >= colors.iter().filter(|&&x| x == c).count()
+        })
+    }
+
+    /// Whether the group can hold a linked set of gems with given colors.
+    ///
+    /// White sockets can hold gems of any color,
+    /// and white gems can be put in sockets of any color.
+    pub fn can_hold(&self, gem_colors: &[Color]) -> bool {
+        if gem_colors.len() > self.size() {
+            return false;
+        }
+        let missing: usize = [Color::Red, Color::Green, Color::Blue].iter()
+            .map(|&c| {
+                let needed = gem_colors.iter().filter(|&&x| x == c).count();
+                needed.saturating_sub(self.count_of(c))
+            })
+            .sum();
+        missing <= self.count_of(Color::White)
+    }
+
+    /// Items socketed in the linked sockets of this group, in socket order,
+    /// with `None`s for empty sockets.
+    #[inline]
+    pub fn items<'g>(&'g self) -> impl Iterator<Item=Option<&'g Item>> + 'g {
+        self.items.iter().map(|i| i.as_ref())
+    }
+
+    /// Items socketed in this group, together with colors of their sockets.
+    pub fn socketed_items<'g>(&'g self) -> impl Iterator<Item=(Color, &'g Item)> + 'g {
+        self.colors.iter().zip(self.items.iter())
+            .filter_map(|(&c, i)| i.as_ref().map(|i| (c, i)))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::ItemSockets;
+    use super::Color::*;
+
+    #[test]
+    fn notation_roundtrip() {
+        for &s in ["", "R", "R-G-B", "R-R-G B W", "B-B A", "A A", "W-W-W-W-W-W"].iter() {
+            let sockets: ItemSockets = s.parse().unwrap();
+            assert_eq!(s, format!("{}", sockets));
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let sockets: ItemSockets = "r-r-g b a".parse().unwrap();
+        assert_eq!(4, sockets.regular_count());
+        assert_eq!(1, sockets.abyssal_count());
+        assert_eq!(3, sockets.max_links());
+        assert_eq!(vec![Red, Red, Green],
+            sockets.groups().next().unwrap().colors().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!("R-X".parse::<ItemSockets>().is_err());
+        assert!("RG".parse::<ItemSockets>().is_err());
+        assert!("R-A".parse::<ItemSockets>().is_err());
+    }
+
+    #[test]
+    fn color_counts() {
+        let sockets: ItemSockets = "R-R-G B W".parse().unwrap();
+        assert_eq!(2, sockets.count_of(Red));
+        assert_eq!(1, sockets.count_of(Green));
+        assert_eq!(1, sockets.count_of(Blue));
+        assert_eq!(1, sockets.count_of(White));
+    }
+
+    #[test]
+    fn has_linked() {
+        let sockets: ItemSockets = "R-R-G B-W".parse().unwrap();
+        assert!(sockets.has_linked(&[Red, Red, Green]));
+        assert!(sockets.has_linked(&[Red, Green]));
+        assert!(sockets.has_linked(&[Blue, White]));
+        assert!(!sockets.has_linked(&[Red, Red, Red]));
+        assert!(!sockets.has_linked(&[Red, Blue]));
+    }
+
+    #[test]
+    fn can_hold() {
+        let sockets: ItemSockets = "R-W-G B".parse().unwrap();
+        assert!(sockets.can_hold(&[Red, Red, Green]));
+        assert!(sockets.can_hold(&[Red, Blue, Green]));
+        assert!(sockets.can_hold(&[White, White]));
+        assert!(!sockets.can_hold(&[Red, Red, Red]));
+        assert!(!sockets.can_hold(&[Red, Green, Blue, Blue]));
+        assert!(!sockets.can_hold(&[Blue, Blue]));
+    }
 }
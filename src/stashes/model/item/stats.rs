@@ -0,0 +1,337 @@
+//! Item statistics derived from its properties, like weapon DPS or armour values.
+//!
+//! The numbers here are computed the same way the game UI and trade sites do it,
+//! including the normalization to maximum quality.
+
+use regex::Regex;
+
+use util::parse_number;
+use super::{Item, ItemCategory};
+
+
+/// Maximum item quality that can be obtained through regular means (i.e. currency).
+///
+/// Values returned by the `*_at_max_quality` methods of `Item` are normalized to this.
+pub const MAX_QUALITY: u8 = 20;
+
+
+/// Damage per second of a weapon, broken down by damage type.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WeaponDps {
+    /// Physical damage per second (pDPS).
+    pub physical: f64,
+    /// Elemental (fire, cold & lightning) damage per second (eDPS).
+    pub elemental: f64,
+    /// Chaos damage per second.
+    pub chaos: f64,
+}
+
+impl WeaponDps {
+    /// Total damage per second of the weapon.
+    #[inline]
+    pub fn total(&self) -> f64 {
+        self.physical + self.elemental + self.chaos
+    }
+}
+
+
+/// Defensive values of an armour item.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Defences {
+    /// Armour rating.
+    pub armour: u32,
+    /// Evasion rating.
+    pub evasion: u32,
+    /// Energy shield.
+    pub energy_shield: u32,
+}
+
+impl Defences {
+    /// Whether the item has no defensive values at all.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.armour == 0 && self.evasion == 0 && self.energy_shield == 0
+    }
+}
+
+
+impl Item {
+    /// Damage per second of the weapon, if this item is one.
+    ///
+    /// The values are taken directly from item properties,
+    /// so they include all the local mods and the current quality of the item.
+    pub fn weapon_dps(&self) -> Option<WeaponDps> {
+        match self.category.as_ref() {
+            Some(&ItemCategory::Weapon(_)) => {}
+            _ => return None,
+        }
+        let aps = self.properties.get_value("Attacks per Second")
+            .and_then(|aps| parse_number(aps).ok())?;
+        let average_damage = |prop: &str| -> f64 {
+            self.properties.get_values(prop).unwrap_or(&[]).iter()
+                .filter_map(|v| parse_range(v))
+                .map(|(min, max)| (min + max) / 2.0)
+                .sum()
+        };
+        Some(WeaponDps {
+            physical: average_damage("Physical Damage") * aps,
+            elemental: average_damage("Elemental Damage") * aps,
+            chaos: average_damage("Chaos Damage") * aps,
+        })
+    }
+
+    /// Damage per second of the weapon, if this item is one,
+    /// with physical damage normalized to `MAX_QUALITY`.
+    ///
+    /// Items with quality at or above the maximum are not adjusted.
+    pub fn weapon_dps_at_max_quality(&self) -> Option<WeaponDps> {
+        let dps = self.weapon_dps()?;
+        let factor = self.max_quality_factor(LOCAL_PHYSICAL_STATS);
+        Some(WeaponDps{physical: dps.physical * factor, ..dps})
+    }
+
+    /// Defensive values of the armour item, if this item is one.
+    ///
+    /// The values are taken directly from item properties,
+    /// so they include all the local mods and the current quality of the item.
+    pub fn defences(&self) -> Option<Defences> {
+        match self.category.as_ref() {
+            Some(&ItemCategory::Armour(_)) => {}
+            _ => return None,
+        }
+        let defence = |prop: &str| -> u32 {
+            self.properties.get_value(prop)
+                .and_then(|v| parse_number(v).ok())
+                .map(|v| v as u32)
+                .unwrap_or(0)
+        };
+        Some(Defences {
+            armour: defence("Armour"),
+            evasion: defence("Evasion Rating"),
+            energy_shield: defence("Energy Shield"),
+        })
+    }
+
+    /// Defensive values of the armour item, if this item is one,
+    /// normalized to `MAX_QUALITY`.
+    ///
+    /// Items with quality at or above the maximum are not adjusted.
+    pub fn defences_at_max_quality(&self) -> Option<Defences> {
+        let defences = self.defences()?;
+        let normalize = |value: u32, stats: &[&str]| -> u32 {
+            (value as f64 * self.max_quality_factor(stats)).round() as u32
+        };
+        Some(Defences {
+            armour: normalize(defences.armour, LOCAL_ARMOUR_STATS),
+            evasion: normalize(defences.evasion, LOCAL_EVASION_STATS),
+            energy_shield: normalize(defences.energy_shield, LOCAL_ENERGY_SHIELD_STATS),
+        })
+    }
+
+    /// Compute the factor by which a quality-affected item stat
+    /// would change if the item had `MAX_QUALITY`.
+    ///
+    /// Quality is additive with local "increased" mods of given `stats`,
+    /// so their values need to be taken into account.
+    fn max_quality_factor(&self, stats: &[&str]) -> f64 {
+        lazy_static! {
+            static ref INCREASED_RE: Regex = Regex::new(r"^(\d+)% increased (.+)$").unwrap();
+        }
+        let quality = *self.quality;
+        if quality >= MAX_QUALITY {
+            return 1.0;
+        }
+        let increased: f64 = self.mods()
+            .filter_map(|m| INCREASED_RE.captures(m.as_str()))
+            .filter(|caps| stats.contains(&&caps[2]))
+            .filter_map(|caps| parse_number(&caps[1]).ok())
+            .sum();
+        (100.0 + increased + MAX_QUALITY as f64) / (100.0 + increased + quality as f64)
+    }
+}
+
+/// Names of stats increased by local weapon mods that are additive with quality.
+const LOCAL_PHYSICAL_STATS: &[&str] = &["Physical Damage"];
+
+/// Names of stats increased by local armour mods that are additive with quality,
+/// for each of the defence types.
+const LOCAL_ARMOUR_STATS: &[&str] = &[
+    "Armour", "Armour and Evasion", "Armour and Energy Shield",
+    "Armour, Evasion and Energy Shield",
+];
+const LOCAL_EVASION_STATS: &[&str] = &[
+    "Evasion Rating", "Armour and Evasion", "Evasion and Energy Shield",
+    "Armour, Evasion and Energy Shield",
+];
+const LOCAL_ENERGY_SHIELD_STATS: &[&str] = &[
+    "Energy Shield", "Armour and Energy Shield", "Evasion and Energy Shield",
+    "Armour, Evasion and Energy Shield",
+];
+
+/// Parse a value range such as "20-63" into a pair of numbers.
+fn parse_range(s: &str) -> Option<(f64, f64)> {
+    let mut parts = s.splitn(2, '-');
+    let min = parse_number(parts.next()?).ok()?;
+    let max = match parts.next() {
+        Some(max) => parse_number(max).ok()?,
+        None => min,
+    };
+    Some((min, max))
+}
+
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use serde_json::from_value;
+    use ::stashes::Item;
+    use super::Defences;
+
+    // Test items below are taken from testdata/public-stash-tabs.json.
+
+    #[test]
+    fn weapon_dps__physical() {
+        let item = from_value::<Item>(json!({
+            "id": "176b5e6f7af0a5bb4b48d7fdafa47501a179f4ea095815a58c82c4b5244b3cdb",
+            "name": "<<set:MS>><<set:M>><<set:S>>Roth's Reach",
+            "typeLine": "Recurve Bow",
+            "ilvl": 71,
+            "frameType": 3,
+            "category": {"weapons": ["bow"]},
+            "properties": [
+                {"name": "Bow", "values": [], "displayMode": 0},
+                {"name": "Quality", "values": [["+17%", 1]], "displayMode": 0, "type": 6},
+                {"name": "Physical Damage", "values": [["20-63", 1]], "displayMode": 0, "type": 9},
+                {"name": "Critical Strike Chance", "values": [["6.50%", 0]], "displayMode": 0, "type": 12},
+                {"name": "Attacks per Second", "values": [["1.31", 1]], "displayMode": 0, "type": 13},
+            ],
+            "explicitMods": [
+                "68% increased Physical Damage",
+                "5% increased Attack Speed",
+                "Skills Chain +1 times",
+                "30% increased Projectile Speed",
+                "34% increased Elemental Damage with Attack Skills",
+            ],
+        })).unwrap();
+
+        let dps = item.weapon_dps().unwrap();
+        assert_close(54.365, dps.physical);
+        assert_close(0.0, dps.elemental);
+        assert_close(54.365, dps.total());
+
+        let dps = item.weapon_dps_at_max_quality().unwrap();
+        assert_close(54.365 * 188.0 / 185.0, dps.physical);
+        assert_close(dps.physical, dps.total());
+    }
+
+    #[test]
+    fn weapon_dps__physical_and_elemental() {
+        let item = from_value::<Item>(json!({
+            "id": "e0828d3d67b53efe5a297dde572d3458bbd8e61fcc25782e326018ce5be3aedd",
+            "name": "<<set:MS>><<set:M>><<set:S>>Shiversting",
+            "typeLine": "Bastard Sword",
+            "ilvl": 76,
+            "frameType": 3,
+            "category": {"weapons": ["twosword"]},
+            "properties": [
+                {"name": "Two Handed Sword", "values": [], "displayMode": 0},
+                {"name": "Quality", "values": [["+5%", 1]], "displayMode": 0, "type": 6},
+                {"name": "Physical Damage", "values": [["30-51", 1]], "displayMode": 0, "type": 9},
+                {"name": "Elemental Damage", "values": [["35-70", 5]], "displayMode": 0, "type": 10},
+                {"name": "Critical Strike Chance", "values": [["5.00%", 0]], "displayMode": 0, "type": 12},
+                {"name": "Attacks per Second", "values": [["1.45", 0]], "displayMode": 0, "type": 13},
+                {"name": "Weapon Range", "values": [["11", 0]], "displayMode": 0, "type": 14},
+            ],
+            "implicitMods": ["18% increased Accuracy Rating"],
+            "explicitMods": [
+                "97% increased Physical Damage",
+                "Adds 35 to 70 Cold Damage",
+                "0.6% of Physical Attack Damage Leeched as Mana",
+                "Cannot be Frozen",
+            ],
+        })).unwrap();
+
+        let dps = item.weapon_dps().unwrap();
+        assert_close(58.725, dps.physical);
+        assert_close(76.125, dps.elemental);
+        assert_close(134.85, dps.total());
+
+        // Quality doesn't affect elemental damage.
+        let dps = item.weapon_dps_at_max_quality().unwrap();
+        assert_close(58.725 * 217.0 / 202.0, dps.physical);
+        assert_close(76.125, dps.elemental);
+    }
+
+    #[test]
+    fn weapon_dps__multiple_elements() {
+        let item = from_value::<Item>(json!({
+            "id": "123abc",
+            "name": "",
+            "typeLine": "Driftwood Wand",
+            "ilvl": 10,
+            "frameType": 0,
+            "category": {"weapons": ["wand"]},
+            "properties": [
+                {"name": "Physical Damage", "values": [["3-6", 0]], "displayMode": 0, "type": 9},
+                {"name": "Elemental Damage", "values": [["1-3", 4], ["2-4", 5]], "displayMode": 0, "type": 10},
+                {"name": "Chaos Damage", "values": [["5-7", 7]], "displayMode": 0, "type": 11},
+                {"name": "Attacks per Second", "values": [["2.00", 0]], "displayMode": 0, "type": 13},
+            ],
+        })).unwrap();
+
+        let dps = item.weapon_dps().unwrap();
+        assert_close(9.0, dps.physical);
+        assert_close(10.0, dps.elemental);
+        assert_close(12.0, dps.chaos);
+        assert_close(31.0, dps.total());
+    }
+
+    #[test]
+    fn defences() {
+        let item = from_value::<Item>(json!({
+            "id": "9d089e1fd53ba860691fcbba9d977002fae2864d4ad4cbf9a6205cc0e9cb88bf",
+            "name": "<<set:MS>><<set:M>><<set:S>>Armageddon Cloak",
+            "typeLine": "Saint's Hauberk",
+            "ilvl": 69,
+            "frameType": 2,
+            "category": {"armour": ["chest"]},
+            "properties": [
+                {"name": "Armour", "values": [["762", 1]], "displayMode": 0, "type": 16},
+                {"name": "Energy Shield", "values": [["306", 1]], "displayMode": 0, "type": 18},
+            ],
+            "explicitMods": [
+                "+23 to Intelligence",
+                "72% increased Armour and Energy Shield",
+                "+105 to maximum Energy Shield",
+                "+35% to Lightning Resistance",
+                "+20% to Chaos Resistance",
+            ],
+        })).unwrap();
+
+        assert_eq!(None, item.weapon_dps());
+        assert_eq!(
+            Some(Defences{armour: 762, evasion: 0, energy_shield: 306}),
+            item.defences());
+        assert_eq!(
+            Some(Defences{armour: 851, evasion: 0, energy_shield: 342}),
+            item.defences_at_max_quality());
+    }
+
+    #[test]
+    fn non_gear_has_no_stats() {
+        let item = from_value::<Item>(json!({
+            "id": "123abc",
+            "name": "",
+            "typeLine": "Example Amazing Item of Testing",
+            "ilvl": 80,
+            "category": "jewels",
+            "frameType": 0,
+        })).unwrap();
+        assert_eq!(None, item.weapon_dps());
+        assert_eq!(None, item.defences());
+    }
+
+    fn assert_close(expected: f64, actual: f64) {
+        assert!((expected - actual).abs() < 1e-6, "expected {}, got {}", expected, actual);
+    }
+}
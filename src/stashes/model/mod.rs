@@ -1,13 +1,17 @@
 //! Module defining the data structures for the PoE API responses.
 
+mod bulk;
 pub mod currency;
 mod item;
 mod label;
 mod price;
 mod stash;
+mod whisper;
 
+pub use self::bulk::{BulkItem, BulkOffer, bulk_offers, match_bulk};
 pub use self::currency::Currency;
 pub use self::item::*;
 pub use self::label::Label;
 pub use self::price::Price;
 pub use self::stash::*;
+pub use self::whisper::WhisperTemplate;
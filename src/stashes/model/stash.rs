@@ -15,7 +15,9 @@ pub struct Stash {
     pub id: String,
     /// League which the stash tab is in.
     pub league: League,
-    /// Label of the stash tab.
+    /// Name of the stash tab, exactly as it's shown in the game.
+    pub name: String,
+    /// Label of the stash tab, i.e. its interpreted name.
     ///
     /// Note that some labels like "~b/o 1 chaos" are special
     /// and indicate the price for every item in the tab.
@@ -36,6 +38,7 @@ impl fmt::Debug for Stash {
         fmt.debug_struct("Stash")
             .field("id", &self.id)
             .field("league", &self.league)
+            .field("name", &self.name)
             .field("label", &self.label)
             .field("type", &self.type_)
             .field("account", &self.account)
@@ -159,4 +162,12 @@ impl StashedItem {
     /// Height of the item in stash tab tiles.
     #[inline]
     pub fn height(&self) -> u64 { self.height }
+
+    /// Whether the dimensions of the item in stash tab
+    /// are the ones expected of its base type.
+    ///
+    /// Returns `None` if the base type or its dimensions are unknown.
+    pub fn size_matches_base(&self) -> Option<bool> {
+        self.item.base_info().and_then(|b| b.size()).map(|size| size == self.size())
+    }
 }
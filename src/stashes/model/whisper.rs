@@ -0,0 +1,209 @@
+//! Trade whisper messages for stashed items.
+
+use regex::{Captures, Regex};
+
+use super::item::{Item, Rarity};
+use super::stash::{Stash, StashedItem};
+
+
+/// Template of a whisper message that is sent to the seller of an item.
+///
+/// Templates may contain the following placeholders:
+///
+/// * `{character}`: name of the seller's character
+/// * `{item}`: name of the item (including gem level & quality for gems)
+/// * `{price}`: price of the item, e.g. "10 chaos"
+/// * `{league}`: name of the league
+/// * `{stash}`: name of the stash tab
+/// * `{left}`, `{top}`: position of the item in the stash tab (counting from 1)
+#[derive(Clone, Debug, PartialEq)]
+pub struct WhisperTemplate {
+    /// Template for items which have a price.
+    priced: String,
+    /// Template for items without a price.
+    unpriced: String,
+}
+
+impl WhisperTemplate {
+    /// Create a new `WhisperTemplate` from the templates
+    /// for priced and unpriced items, respectively.
+    #[inline]
+    pub fn new<P: Into<String>, U: Into<String>>(priced: P, unpriced: U) -> Self {
+        WhisperTemplate{priced: priced.into(), unpriced: unpriced.into()}
+    }
+}
+
+impl Default for WhisperTemplate {
+    /// The English whisper template, as used by the official trade site.
+    fn default() -> Self {
+        WhisperTemplate::new(
+            "@{character} Hi, I would like to buy your {item} listed for {price} \
+             in {league} (stash tab \"{stash}\"; position: left {left}, top {top})",
+            "@{character} Hi, I would like to buy your {item} \
+             in {league} (stash tab \"{stash}\"; position: left {left}, top {top})")
+    }
+}
+
+
+impl StashedItem {
+    /// Whisper message to send to the seller of this item
+    /// in order to buy it, as generated by the official trade site.
+    ///
+    /// The item's price is taken from its label or, failing that,
+    /// from the label of the stash tab.
+    ///
+    /// Returns `None` if the seller's character is unknown.
+    #[inline]
+    pub fn whisper(&self, stash: &Stash) -> Option<String> {
+        self.whisper_with(stash, &WhisperTemplate::default())
+    }
+
+    /// Whisper message to send to the seller of this item, formatted using given template.
+    ///
+    /// Returns `None` if the seller's character is unknown.
+    pub fn whisper_with(&self, stash: &Stash, template: &WhisperTemplate) -> Option<String> {
+        lazy_static! {
+            static ref PLACEHOLDER_RE: Regex = Regex::new(r"\{(\w+)\}").unwrap();
+        }
+        let character = stash.last_character.as_ref()?;
+        let price = self.label().and_then(|l| l.price()).or_else(|| stash.label.price());
+        let text = match price {
+            Some(_) => &template.priced,
+            None => &template.unpriced,
+        };
+        let (x, y) = self.position();
+        // Placeholders are substituted in a single pass,
+        // so that the substituted values (e.g. stash tab names) are never interpreted.
+        let whisper = PLACEHOLDER_RE.replace_all(text, |caps: &Captures| match &caps[1] {
+            "character" => character.clone(),
+            "item" => listing_name(self),
+            "price" => price.map(|p| p.to_string()).unwrap_or_default(),
+            "league" => stash.league.to_string(),
+            "stash" => stash.name.clone(),
+            "left" => (x + 1).to_string(),
+            "top" => (y + 1).to_string(),
+            _ => caps[0].to_owned(),
+        });
+        Some(whisper.into_owned())
+    }
+}
+
+/// Name of the item as it's referred to in whisper messages.
+fn listing_name(item: &Item) -> String {
+    if let Some(gem) = item.as_gem() {
+        return format!("level {} {}% {}", gem.level, item.quality.0, item.base);
+    }
+    match item.name.as_ref() {
+        // Magic items have their full name (including the base type) as `name`.
+        Some(name) if item.rarity == Rarity::Magic => name.clone(),
+        Some(name) if !name.is_empty() => format!("{} {}", name, item.base),
+        _ => item.base.clone(),
+    }
+}
+
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use serde_json::from_value;
+    use super::super::stash::Stash;
+    use super::WhisperTemplate;
+
+    #[test]
+    fn whisper() {
+        let stash: Stash = from_value(json!({
+            "id": "0123abc",
+            "accountName": "Seller", "lastCharacterName": "Seller_Char",
+            "stash": "sale", "stashType": "PremiumStash",
+            "items": [{
+                "id": "abc123",
+                "name": "Tabula Rasa", "typeLine": "Simple Robe", "ilvl": 70,
+                "category": {"armour": ["chest"]}, "frameType": 3,
+                "note": "~price 10 chaos", "league": "Standard",
+                "x": 2, "y": 4, "w": 2, "h": 3,
+            }],
+        })).unwrap();
+        assert_eq!(
+            Some("@Seller_Char Hi, I would like to buy your Tabula Rasa Simple Robe \
+                  listed for 10 chaos in Standard (stash tab \"sale\"; position: left 3, top 5)"
+                  .to_owned()),
+            stash.items[0].whisper(&stash));
+    }
+
+    #[test]
+    fn whisper__stash_price() {
+        let stash: Stash = from_value(json!({
+            "id": "0123abc",
+            "accountName": "Seller", "lastCharacterName": "Seller_Char",
+            "stash": "~b/o 1 exa", "stashType": "PremiumStash",
+            "items": [{
+                "id": "abc123",
+                "name": "Tabula Rasa", "typeLine": "Simple Robe", "ilvl": 70,
+                "category": {"armour": ["chest"]}, "frameType": 3,
+                "note": "", "league": "Standard",
+                "x": 2, "y": 4, "w": 2, "h": 3,
+            }],
+        })).unwrap();
+        let whisper = stash.items[0].whisper(&stash).unwrap();
+        // Currency is referred to by its canonical ID, like the trade site does.
+        assert!(whisper.contains(
+            "listed for 1 exalted in Standard (stash tab \"~b/o 1 exa\";"));
+    }
+
+    #[test]
+    fn whisper__placeholder_in_stash_name() {
+        let stash: Stash = from_value(json!({
+            "id": "0123abc",
+            "accountName": "Seller", "lastCharacterName": "Seller_Char",
+            "stash": "{left}", "stashType": "PremiumStash",
+            "items": [{
+                "id": "abc123",
+                "name": "Tabula Rasa", "typeLine": "Simple Robe", "ilvl": 70,
+                "category": {"armour": ["chest"]}, "frameType": 3,
+                "note": "~price 10 chaos", "league": "Standard",
+                "x": 2, "y": 4, "w": 2, "h": 3,
+            }],
+        })).unwrap();
+        let whisper = stash.items[0].whisper(&stash).unwrap();
+        assert!(whisper.ends_with("(stash tab \"{left}\"; position: left 3, top 5)"));
+    }
+
+    #[test]
+    fn whisper__unknown_character() {
+        let stash: Stash = from_value(json!({
+            "id": "0123abc",
+            "accountName": "Seller", "lastCharacterName": null,
+            "stash": "sale", "stashType": "PremiumStash",
+            "items": [{
+                "id": "abc123",
+                "name": "Tabula Rasa", "typeLine": "Simple Robe", "ilvl": 70,
+                "category": {"armour": ["chest"]}, "frameType": 3,
+                "note": "~price 10 chaos", "league": "Standard",
+                "x": 2, "y": 4, "w": 2, "h": 3,
+            }],
+        })).unwrap();
+        assert_eq!(None, stash.items[0].whisper(&stash));
+    }
+
+    #[test]
+    fn whisper_with_template() {
+        let stash: Stash = from_value(json!({
+            "id": "0123abc",
+            "accountName": "Seller", "lastCharacterName": "Verkaeufer",
+            "stash": "sale", "stashType": "PremiumStash",
+            "items": [{
+                "id": "abc123",
+                "name": "Tabula Rasa", "typeLine": "Simple Robe", "ilvl": 70,
+                "category": {"armour": ["chest"]}, "frameType": 3,
+                "note": "", "league": "Standard",
+                "x": 2, "y": 4, "w": 2, "h": 3,
+            }],
+        })).unwrap();
+        let template = WhisperTemplate::new(
+            "@{character} Hi, ich möchte '{item}' zum Preis von {price} kaufen",
+            "@{character} Hi, ich möchte '{item}' in {league} kaufen");
+        assert_eq!(
+            Some("@Verkaeufer Hi, ich möchte 'Tabula Rasa Simple Robe' in Standard kaufen".to_owned()),
+            stash.items[0].whisper_with(&stash, &template));
+    }
+}